@@ -12,6 +12,8 @@ type Driver = ParseDriver<DriverState>;
 type Pos<'a> = SlicePos<'a, u8>;
 type Progress<'a, T, E> = pahs::Progress<Pos<'a>, T, E>;
 
+// only printed via `Debug`
+#[allow(dead_code)]
 #[derive(Debug, Default)]
 struct DriverState {
     next: Option<()>,
@@ -44,6 +46,8 @@ fn main() {
     }
 }
 
+// only printed via `Debug`
+#[allow(dead_code)]
 #[derive(Debug)]
 enum MsgPack<'a> {
     Nil,
@@ -72,6 +76,8 @@ enum MsgPack<'a> {
 }
 
 impl<'a> MsgPack<'a> {
+    // the markers of the sized types are listed one by one, like their sizes below
+    #[allow(clippy::manual_range_patterns)]
     fn parse(pd: &mut Driver, start_pos: Pos<'a>) -> Progress<'a, Self, Error> {
         use MsgPack::*;
         let (pos, first_byte) = pahs!(start_pos.take1().map_err(|_| Error::NoNextElement));
@@ -0,0 +1,90 @@
+//! Derive macros for pahs
+
+#![deny(rust_2018_idioms)]
+#![warn(missing_docs)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields};
+
+/// Derives `pahs::slice::Pod` for a struct.
+///
+/// The struct has to be `#[repr(C)]` or `#[repr(transparent)]`, must not be generic,
+/// all of its fields have to implement `Pod` themselves, and it must not contain any padding.
+/// All of these requirements are checked at compile time.
+#[proc_macro_derive(Pod)]
+pub fn derive_pod(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match pod_impl(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn pod_impl(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "`Pod` cannot be derived for generic types",
+        ));
+    }
+
+    if !has_stable_repr(input)? {
+        return Err(Error::new(
+            name.span(),
+            "`Pod` can only be derived for `#[repr(C)]` or `#[repr(transparent)]` structs",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect::<Vec<_>>(),
+            Fields::Unnamed(fields) => fields.unnamed.iter().collect(),
+            Fields::Unit => Vec::new(),
+        },
+        _ => {
+            return Err(Error::new(
+                name.span(),
+                "`Pod` can only be derived for structs",
+            ))
+        }
+    };
+    let field_types = fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+
+    Ok(quote! {
+        // every field has to be `Pod` itself
+        const _: fn() = || {
+            fn assert_field_is_pod<T: ::pahs::slice::Pod>() {}
+            #(assert_field_is_pod::<#field_types>();)*
+        };
+
+        // the fields have to cover the whole struct, so there is no padding
+        const _: [(); 0] = [(); (::std::mem::size_of::<#name>()
+            != 0 #(+ ::std::mem::size_of::<#field_types>())*) as usize];
+
+        unsafe impl ::pahs::slice::Pod for #name {}
+    })
+}
+
+fn has_stable_repr(input: &DeriveInput) -> Result<bool, Error> {
+    let mut stable = false;
+
+    for attr in input.attrs.iter().filter(|a| a.path.is_ident("repr")) {
+        if let syn::Meta::List(list) = attr.parse_meta()? {
+            for nested in list.nested {
+                if let syn::NestedMeta::Meta(meta) = nested {
+                    if meta.path().is_ident("C") || meta.path().is_ident("transparent") {
+                        stable = true;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(stable)
+}
@@ -4,7 +4,132 @@
 #![warn(missing_docs)]
 #![warn(clippy::missing_inline_in_public_items)]
 
-use pahs::Progress;
+use pahs::diagnostic::LineColumn;
+use pahs::slice::BytePos;
+use pahs::{ParseDriver, Pos, Progress};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use pahs::Progress;
+    pub use snafu::{IntoError, NoneError};
+}
+
+/// Fails the current parser at `pos` with a snafu leaf error if `cond` is false.
+///
+/// `context` has to be the context selector of that leaf error. The error is converted
+/// via `From`, like in [`pahs!`](pahs::pahs). Unlike `snafu::ensure!`, it returns a failed
+/// [`Progress`](pahs::Progress) instead of an `Err`.
+///
+/// ```
+/// use pahs::slice::BytePos;
+/// use pahs::Progress;
+/// use pahs_snafu::ensure_at;
+/// use snafu::Snafu;
+///
+/// #[derive(Debug, Snafu)]
+/// enum Error {
+///     #[snafu(display("length {} is too long", len))]
+///     TooLong { len: u8 },
+/// }
+///
+/// fn check_len(pos: BytePos<'_>, len: u8) -> Progress<BytePos<'_>, u8, Error> {
+///     ensure_at!(pos, len <= 16, TooLong { len });
+///     Progress::success(pos, len)
+/// }
+///
+/// let pos = BytePos::new(b"");
+/// assert_eq!(check_len(pos, 16).unwrap().1, 16);
+/// assert_eq!(check_len(pos, 17).unwrap_err().1.to_string(), "length 17 is too long");
+/// ```
+#[macro_export]
+macro_rules! ensure_at {
+    ($pos:expr, $cond:expr, $context:expr $(,)?) => {
+        if !$cond {
+            return $crate::__private::Progress::failure(
+                $pos,
+                ::std::convert::From::from($crate::__private::IntoError::into_error(
+                    $context,
+                    $crate::__private::NoneError,
+                )),
+            );
+        }
+    };
+}
+
+/// Locations that can be computed from a position, for use in context selectors.
+///
+/// See [`snafu_context`](snafu_context).
+pub trait FromPos<P> {
+    /// Computes the location of `pos`.
+    fn from_pos(pos: &P) -> Self;
+}
+
+impl<P: Pos> FromPos<P> for usize {
+    /// The offset of the position.
+    #[inline]
+    fn from_pos(pos: &P) -> Self {
+        pos.offset()
+    }
+}
+
+impl<'a> FromPos<BytePos<'a>> for LineColumn {
+    /// The line and column of the position in the input, assuming it is UTF-8 encoded.
+    #[inline]
+    fn from_pos(pos: &BytePos<'a>) -> Self {
+        LineColumn::of_byte_offset(pos.origin(), pos.offset)
+    }
+}
+
+/// Wraps the specified `parser`, mapping its errors to a snafu error with the previous error
+/// as the source.
+///
+/// `context_fn` has to be a function that returns the context selector of that error.
+/// It receives the location of the failure, computed via [`FromPos`](FromPos), like the
+/// offset (`usize`) or a [`LineColumn`](pahs::diagnostic::LineColumn). Annotate the
+/// parameter type of `context_fn` to pick one.
+///
+/// Unlike [`ProgressSnafuExt::snafu`](ProgressSnafuExt::snafu), the context selector is
+/// attached once, instead of mapping every produced [`Progress`](pahs::Progress).
+#[inline]
+pub fn snafu_context<S, P, T, E, F, L, C, Fc, E2>(
+    mut parser: F,
+    mut context_fn: Fc,
+) -> impl FnMut(&mut ParseDriver<S>, P) -> Progress<P, T, E2>
+where
+    P: Pos,
+    F: FnMut(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
+    L: FromPos<P>,
+    C: snafu::IntoError<E2, Source = E>,
+    Fc: FnMut(L) -> C,
+    E2: std::error::Error + snafu::ErrorCompat,
+{
+    move |pd, pos| {
+        parser(pd, pos).map_err_with_pos(|e, pos| context_fn(L::from_pos(&pos)).into_error(e))
+    }
+}
+
+/// Wraps the specified `parser`, replacing its errors with a snafu leaf error.
+///
+/// `context_fn` has to be a function that returns the context selector of that leaf error.
+/// It receives the location of the failure, see [`snafu_context`](snafu_context).
+#[inline]
+pub fn snafu_leaf_context<S, P, T, E, F, L, C, Fc, E2>(
+    mut parser: F,
+    mut context_fn: Fc,
+) -> impl FnMut(&mut ParseDriver<S>, P) -> Progress<P, T, E2>
+where
+    P: Pos,
+    F: FnMut(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
+    L: FromPos<P>,
+    C: snafu::IntoError<E2, Source = snafu::NoneError>,
+    Fc: FnMut(L) -> C,
+    E2: std::error::Error + snafu::ErrorCompat,
+{
+    move |pd, pos| {
+        parser(pd, pos)
+            .map_err_with_pos(|_, pos| context_fn(L::from_pos(&pos)).into_error(snafu::NoneError))
+    }
+}
 
 /// Extension trait for pahs' [`Progress`](pahs::Progress) type for integration with snafu.
 pub trait ProgressSnafuExt<P, T, E> {
@@ -77,3 +202,64 @@ impl<P, T, E> ProgressSnafuExt<P, T, E> for Progress<P, T, E> {
         self.map_err_with_pos(|_, pos| context_fn(pos).into_error(snafu::NoneError))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use pahs::diagnostic::LineColumn;
+    use pahs::slice::num::u8_le;
+    use pahs::slice::{BytePos, NotEnoughDataError};
+    use pahs::{ParseDriver, Progress};
+    use snafu::Snafu;
+
+    use super::*;
+
+    #[derive(Debug, Snafu)]
+    enum Error {
+        #[snafu(display("missing length at offset {}", offset))]
+        MissingLength {
+            offset: usize,
+            source: NotEnoughDataError,
+        },
+        #[snafu(display("missing tag at {}", location))]
+        MissingTag { location: LineColumn },
+        #[snafu(display("length {} is too long", len))]
+        TooLong { len: u8 },
+    }
+
+    fn short_string<'a>(
+        pd: &mut ParseDriver,
+        pos: BytePos<'a>,
+    ) -> Progress<BytePos<'a>, u8, Error> {
+        let (pos, len) = pahs::pahs!(snafu_context(u8_le, |offset: usize| MissingLength {
+            offset
+        })(pd, pos));
+        ensure_at!(pos, len <= 16, TooLong { len });
+        Progress::success(pos, len)
+    }
+
+    #[test]
+    fn it_wraps_parsers_with_context_selectors() {
+        let pd = &mut ParseDriver::new();
+
+        let (_, len) = short_string(pd, BytePos::new(&[4])).unwrap();
+        assert_eq!(len, 4);
+
+        let (_, err) = short_string(pd, BytePos::new(&[4]).advance_by(1)).unwrap_err();
+        assert_eq!(err.to_string(), "missing length at offset 1");
+
+        let (pos, err) = short_string(pd, BytePos::new(&[17])).unwrap_err();
+        assert_eq!(pos.offset, 1);
+        assert_eq!(err.to_string(), "length 17 is too long");
+    }
+
+    #[test]
+    fn it_passes_line_and_column_to_leaf_selectors() {
+        let pd = &mut ParseDriver::new();
+        let pos = BytePos::new(b"a\nbc").advance_by(4);
+
+        let (_, err) =
+            snafu_leaf_context(u8_le, |location: LineColumn| MissingTag { location })(pd, pos)
+                .unwrap_err();
+        assert_eq!(err.to_string(), "missing tag at 2:3");
+    }
+}
@@ -6,11 +6,25 @@ pub use alternate::*;
 mod n_or_more;
 pub use n_or_more::*;
 
+mod context;
+pub use context::*;
+
 mod count;
 pub use count::*;
 
+mod nested;
+pub use nested::*;
+
 mod optional;
 pub use optional::*;
 
+mod recover;
+pub use recover::*;
+
+mod rule;
+pub use rule::*;
+
 mod sequence;
+// `sequence` only defines exported macros for now
+#[allow(unused_imports)]
 pub use sequence::*;
@@ -1,10 +1,25 @@
+use std::fmt;
+
 use crate::error_accumulator::ErrorAccumulator;
-use crate::{ParseDriver, Pos, Progress, Recoverable};
+use crate::{DriverError, ErrorKind, ParseDriver, Pos, Progress, Recoverable};
 
 /// Try all parsers supplied via [`one`](crate::combinators::Alternate::one) in order
 /// and return the value of the first one that successfully parses.
 ///
 /// If none of the parsers were successful, returns the accumulated error.
+///
+/// Only errors of kind [`Backtrack`](crate::ErrorKind::Backtrack) cause the next parser to
+/// be tried. Any other kind skips all further parsers, failing with that error.
+///
+/// If a branch succeeds after others failed, the errors of the failed branches are recorded via
+/// [`ParseDriver::record_failure`](ParseDriver::record_failure) instead of being accumulated.
+///
+/// While the driver detects ambiguities (see
+/// [`ParseDriver::detect_ambiguities`](ParseDriver::detect_ambiguities)), all branches after a
+/// successful one are run as well, only to find out if they match too. Their results are
+/// discarded, and so are their recovered errors, recorded failures and ambiguities. Failures
+/// other than [`Backtrack`](crate::ErrorKind::Backtrack) are reported as part of the
+/// [`Ambiguity`](Ambiguity), as reordering the branches would change the outcome.
 #[must_use]
 #[derive(Debug)]
 pub struct Alternate<'pd, P: 'pd, T, E: 'pd, S, A: 'pd = ()> {
@@ -12,6 +27,70 @@ pub struct Alternate<'pd, P: 'pd, T, E: 'pd, S, A: 'pd = ()> {
     current: Option<Progress<P, T, E>>,
     pos: P,
     err_accumulator: A,
+    /// Errors of failed branches, held back while the driver tracks the furthest failure
+    failed: Vec<(P, E)>,
+    /// The number of branches run so far
+    branches: usize,
+    /// The successful branches, recorded while the driver detects ambiguities
+    matches: Vec<BranchMatch>,
+    /// The irrecoverable failures of branches after a successful one
+    shadowed_failures: Vec<ShadowedFailure>,
+}
+
+/// Multiple branches of an [`Alternate`](Alternate) matching at the same offset, or branches
+/// after the matching one failing irrecoverably, as detected via
+/// [`ParseDriver::detect_ambiguities`](ParseDriver::detect_ambiguities).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ambiguity {
+    /// The offset at which the branches were tried
+    pub offset: usize,
+    /// All matching branches, in order. Only the first one is used.
+    pub matches: Vec<BranchMatch>,
+    /// The branches after the first matching one that failed with anything but
+    /// [`Backtrack`](crate::ErrorKind::Backtrack), in order
+    pub failures: Vec<ShadowedFailure>,
+}
+
+/// A matching branch of an ambiguous [`Alternate`](Alternate), see [`Ambiguity`](Ambiguity).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BranchMatch {
+    /// The index of the branch, `0` for the first branch
+    pub branch: usize,
+    /// The number of elements the branch consumed
+    pub consumed: usize,
+}
+
+/// A branch after the matching one of an [`Alternate`](Alternate) that failed irrecoverably,
+/// see [`Ambiguity`](Ambiguity).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ShadowedFailure {
+    /// The index of the branch, `0` for the first branch
+    pub branch: usize,
+    /// The offset at which the branch failed
+    pub offset: usize,
+    /// The kind of the failure
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for Ambiguity {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ambiguous alternate at offset {}: ", self.offset)?;
+        for (i, m) in self.matches.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "branch {} consumes {}", m.branch, m.consumed)?;
+        }
+        for failure in &self.failures {
+            write!(
+                f,
+                ", branch {} fails with {:?} at offset {}",
+                failure.branch, failure.kind, failure.offset
+            )?;
+        }
+        Ok(())
+    }
 }
 
 impl<'pd, P, T, E, S, A> Alternate<'pd, P, T, E, S, A>
@@ -20,11 +99,60 @@ where
     E: Recoverable,
     A: ErrorAccumulator<P, E>,
 {
+    fn run_branch<F>(&mut self, parser: F) -> Progress<P, T, E>
+    where
+        F: FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
+    {
+        if let Some(err) = self.driver.cancelled(self.pos) {
+            return Progress::failure(self.pos, err);
+        }
+
+        let branch = self.branches;
+        self.branches += 1;
+
+        let id = self
+            .driver
+            .trace_enter(|| format!("alternate branch {}", branch).into(), self.pos);
+        let progress = self.driver.budgeted(self.pos, parser);
+        self.driver.trace_exit(id, &progress, |_| None);
+
+        if progress.is_ok() && self.driver.detects_ambiguities() {
+            self.matches.push(BranchMatch {
+                branch,
+                consumed: progress.pos.offset().saturating_sub(self.pos.offset()),
+            });
+        }
+
+        progress
+    }
+
+    /// Runs a branch after a successful one, only to detect ambiguities.
+    fn run_shadowed<F>(&mut self, parser: F)
+    where
+        F: FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
+    {
+        let branch = self.branches;
+        let saved = self.driver.save_side_effects();
+        let progress = self.run_branch(parser);
+        self.driver.restore_side_effects(saved);
+
+        if let Err(err) = &progress.status {
+            let kind = err.kind();
+            if kind != ErrorKind::Backtrack {
+                self.shadowed_failures.push(ShadowedFailure {
+                    branch,
+                    offset: progress.pos.offset(),
+                    kind,
+                });
+            }
+        }
+    }
+
     fn run_one<F>(&mut self, parser: F)
     where
         F: FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
     {
-        self.current = Some(parser(self.driver, self.pos))
+        self.current = Some(self.run_branch(parser))
     }
 
     /// Creates a new `Alternate` with the specified error accumulator.
@@ -35,6 +163,10 @@ where
             current: None,
             pos,
             err_accumulator,
+            failed: Vec::new(),
+            branches: 0,
+            matches: Vec::new(),
+            shadowed_failures: Vec::new(),
         }
     }
 
@@ -47,21 +179,31 @@ where
         match &mut self.current {
             None => self.run_one(parser),
             Some(Progress { status: Ok(..), .. }) => {
-                // matched! skip all further parsers
+                // matched! skip all further parsers, unless looking for ambiguities
+                if self.driver.detects_ambiguities() {
+                    self.run_shadowed(parser);
+                }
             }
-            Some(Progress { status: Err(e), .. }) if e.recoverable() => {
-                // just matched on it, unwrap can't fail
-                let current = self.current.take().unwrap();
-
-                // accumulate it
-                let _ = self.err_accumulator.add_progress(current);
+            Some(Progress { status: Err(e), .. }) if e.kind() == ErrorKind::Backtrack => {
+                if let Some(Progress {
+                    pos,
+                    status: Err(err),
+                }) = self.current.take()
+                {
+                    if self.driver.tracks_furthest_failure() {
+                        // only known to be discarded once another branch succeeds
+                        self.failed.push((pos, err));
+                    } else {
+                        self.err_accumulator.add_err(err, pos);
+                    }
+                }
 
                 self.run_one(parser)
             }
             Some(Progress {
                 status: Err(..), ..
             }) => {
-                // irrecoverable, skip all further parsers
+                // cut, incomplete or fatal, skip all further parsers
             }
         }
 
@@ -70,34 +212,75 @@ where
 
     /// Completes this `Alternate`, returning the progress of the first successful branch.
     ///
-    /// If none of parsers were successful, it returns the accumulated errors
+    /// If none of parsers were successful, it returns the accumulated errors.
     ///
-    /// Panics if no parser was run via [`one`](Alternate::one).
+    /// If no parser was run via [`one`](Alternate::one), it fails at the initial position with
+    /// [`DriverError::NoAlternatives`](crate::DriverError::NoAlternatives), converted via
+    /// [`Recoverable::from_driver_error`](Recoverable::from_driver_error). See
+    /// [`try_finish`](Alternate::try_finish) to handle that case yourself.
     #[inline]
     pub fn finish(self) -> Progress<P, T, A::Accumulated> {
         let mut err_accumulator = self.err_accumulator;
 
-        // accumulate the final progress
-        let progress = err_accumulator.add_progress(self.current.unwrap());
+        if self.matches.len() > 1 || !self.shadowed_failures.is_empty() {
+            self.driver.record_ambiguity(Ambiguity {
+                offset: self.pos.offset(),
+                matches: self.matches,
+                failures: self.shadowed_failures,
+            });
+        }
+
+        if let Some(Progress { status: Ok(..), .. }) = self.current {
+            for (pos, err) in self.failed {
+                self.driver.record_failure(pos, &err);
+            }
+        } else {
+            for (pos, err) in self.failed {
+                err_accumulator.add_err(err, pos);
+            }
+        }
+
+        match self.current {
+            // accumulate the final progress
+            Some(current) => err_accumulator
+                .add_progress(current)
+                .map_err(|_| err_accumulator.finish()),
+            None => {
+                let err = DriverError::NoAlternatives {
+                    offset: self.pos.offset(),
+                };
+                err_accumulator.add_err(E::from_driver_error(err), self.pos);
+                Progress::failure(self.pos, err_accumulator.finish())
+            }
+        }
+    }
 
-        progress.map_err(|_| err_accumulator.finish())
+    /// Completes this `Alternate`, returning the progress of the first successful branch.
+    ///
+    /// If none of parsers were successful, it returns the accumulated errors.
+    ///
+    /// Returns `None` if no parser was run via [`one`](Alternate::one).
+    #[inline]
+    pub fn try_finish(self) -> Option<Progress<P, T, A::Accumulated>> {
+        if self.current.is_some() {
+            Some(self.finish())
+        } else {
+            None
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::error_accumulator::AllErrorsAccumulator;
-    use crate::slice::BytePos;
-    use crate::{ParseDriver, Recoverable};
+    use std::cell::Cell;
 
-    #[derive(Debug, PartialEq)]
-    pub struct TestError(bool);
+    use crate::combinators::rule;
+    use crate::error_accumulator::{AllErrorsAccumulator, LastErrorOnly};
+    use crate::slice::BytePos;
+    use crate::testing::fixtures::TestError;
+    use crate::{Budget, DriverError, ErrorKind, ParseDriver, Recoverable};
 
-    impl Recoverable for TestError {
-        fn recoverable(&self) -> bool {
-            self.0
-        }
-    }
+    use super::{Ambiguity, BranchMatch, ShadowedFailure};
 
     #[test]
     fn it_returns_the_first_successful_branch() {
@@ -154,4 +337,223 @@ mod test {
         // last branch won't run because the third one was irrecoverable
         assert_eq!(err, &[TestError(true), TestError(true), TestError(false)]);
     }
+
+    #[derive(Debug, PartialEq)]
+    pub struct KindError(ErrorKind);
+
+    impl Recoverable for KindError {
+        fn kind(&self) -> ErrorKind {
+            self.0
+        }
+
+        fn from_driver_error(_err: DriverError) -> Self {
+            KindError(ErrorKind::Fatal)
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct DriverFailure(DriverError);
+
+    impl Recoverable for DriverFailure {
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Fatal
+        }
+
+        fn from_driver_error(err: DriverError) -> Self {
+            DriverFailure(err)
+        }
+    }
+
+    #[test]
+    fn it_only_backtracks_on_backtrack_errors() {
+        let input = &[0u8, 1, 2, 3, 4];
+        let pos = BytePos::new(input);
+        let pd = &mut ParseDriver::new();
+
+        for &kind in &[ErrorKind::Cut, ErrorKind::Incomplete, ErrorKind::Fatal] {
+            let (_, err) = pd
+                .alternate(pos)
+                .one(|_, pos| pos.failure(KindError(ErrorKind::Backtrack)))
+                .one(|_, pos| pos.failure(KindError(kind)))
+                .one(|_, pos| pos.advance_by(1).success(0u8))
+                .finish()
+                .unwrap_err();
+
+            assert_eq!(err, KindError(kind));
+            assert!(!err.recoverable());
+        }
+    }
+
+    #[test]
+    fn it_handles_alternates_without_branches() {
+        let input = &[0u8, 1, 2, 3, 4];
+        let pos = BytePos::new(input).advance_by(1);
+        let pd = &mut ParseDriver::new();
+
+        let progress = pd
+            .alternate_accumulate_errors::<_, (), DriverFailure, _>(pos, LastErrorOnly::new())
+            .try_finish();
+        assert!(progress.is_none());
+
+        let expected = DriverFailure(DriverError::NoAlternatives { offset: 1 });
+
+        let (res_pos, err) = pd
+            .alternate_accumulate_errors::<_, (), DriverFailure, _>(pos, LastErrorOnly::new())
+            .finish()
+            .unwrap_err();
+        assert_eq!(res_pos.offset, 1);
+        assert_eq!(err, expected);
+
+        let (res_pos, errs) = pd
+            .alternate_accumulate_errors::<_, (), DriverFailure, _>(
+                pos,
+                AllErrorsAccumulator::new(),
+            )
+            .finish()
+            .unwrap_err();
+        assert_eq!(res_pos.offset, 1);
+        assert_eq!(errs, &[expected]);
+    }
+
+    #[test]
+    fn it_detects_ambiguities() {
+        let input = &[0u8, 1, 2, 3, 4];
+        let pos = BytePos::new(input).advance_by(1);
+        let pd = &mut ParseDriver::new();
+        let runs = Cell::new(0);
+
+        let alternate = |pd: &mut ParseDriver| {
+            pd.alternate(pos)
+                .one(|_, pos| pos.advance_by(1).success(0u8))
+                .one(|_, pos| pos.failure(TestError(true)))
+                .one(|_, pos| {
+                    runs.set(runs.get() + 1);
+                    pos.advance_by(2).success(2u8)
+                })
+                .one(|_, pos| pos.advance_by(1).success(3u8))
+                .finish()
+                .unwrap()
+        };
+
+        // shadowed branches don't run by default
+        let (res_pos, val) = alternate(pd);
+        assert_eq!((res_pos.offset, val), (2, 0));
+        assert_eq!(runs.get(), 0);
+        assert!(pd.take_ambiguities().is_none());
+
+        pd.detect_ambiguities();
+        assert!(pd.detects_ambiguities());
+        let (res_pos, val) = alternate(pd);
+        assert_eq!((res_pos.offset, val), (2, 0));
+        assert_eq!(runs.get(), 1);
+
+        let ambiguities = pd.take_ambiguities().unwrap();
+        assert_eq!(
+            ambiguities,
+            &[Ambiguity {
+                offset: 1,
+                matches: vec![
+                    BranchMatch {
+                        branch: 0,
+                        consumed: 1
+                    },
+                    BranchMatch {
+                        branch: 2,
+                        consumed: 2
+                    },
+                    BranchMatch {
+                        branch: 3,
+                        consumed: 1
+                    },
+                ],
+                failures: Vec::new(),
+            }]
+        );
+        assert_eq!(
+            ambiguities[0].to_string(),
+            "ambiguous alternate at offset 1: branch 0 consumes 1, branch 2 consumes 2, \
+             branch 3 consumes 1"
+        );
+        assert!(!pd.detects_ambiguities());
+    }
+
+    #[test]
+    fn it_discards_the_side_effects_of_shadowed_branches() {
+        let input = &[0u8, 1, 2, 3, 4];
+        let pos = BytePos::new(input);
+        let pd = &mut ParseDriver::with_state(Vec::new());
+        pd.track_furthest_failure();
+        pd.set_budget(Budget::Invocations(10));
+        pd.enable_trace();
+        pd.enable_profiling();
+        pd.detect_ambiguities();
+
+        let (res_pos, val) = pd
+            .alternate(pos)
+            .one(|pd, pos| {
+                pd.state.push(0);
+                pos.advance_by(1).success(0u8)
+            })
+            .one(|pd, pos| {
+                pd.state.push(1);
+                let pos = pos.advance_by(2);
+                pd.record_failure(pos, &KindError(ErrorKind::Backtrack));
+                rule("shadowed", |_, pos: BytePos<'_>| {
+                    pos.failure(KindError(ErrorKind::Fatal))
+                })(pd, pos)
+            })
+            .one(|_, pos| pos.failure(KindError(ErrorKind::Backtrack)))
+            .finish()
+            .unwrap();
+        assert_eq!((res_pos.offset, val), (1, 0));
+
+        // only the first branch is charged, traced and changed the state
+        assert_eq!(pd.state, &[0]);
+        assert_eq!(pd.remaining_budget(), Some(9));
+        assert_eq!(pd.take_trace().unwrap().nodes().len(), 1);
+        assert_eq!(pd.take_profile().unwrap().rule("shadowed"), None);
+        assert!(pd.take_furthest_failure().is_none());
+
+        // the irrecoverable failure is reported instead
+        let ambiguities = pd.take_ambiguities().unwrap();
+        assert_eq!(
+            ambiguities,
+            &[Ambiguity {
+                offset: 0,
+                matches: vec![BranchMatch {
+                    branch: 0,
+                    consumed: 1
+                }],
+                failures: vec![ShadowedFailure {
+                    branch: 1,
+                    offset: 2,
+                    kind: ErrorKind::Fatal
+                }],
+            }]
+        );
+        assert_eq!(
+            ambiguities[0].to_string(),
+            "ambiguous alternate at offset 0: branch 0 consumes 1, \
+             branch 1 fails with Fatal at offset 2"
+        );
+    }
+
+    #[test]
+    fn it_does_not_report_unambiguous_alternates() {
+        let input = &[0u8, 1, 2, 3, 4];
+        let pos = BytePos::new(input);
+        let pd = &mut ParseDriver::new();
+        pd.detect_ambiguities();
+
+        let (res_pos, val) = pd
+            .alternate(pos)
+            .one(|_, pos| pos.failure(TestError(true)))
+            .one(|_, pos| pos.advance_by(1).success(1u8))
+            .one(|_, pos| pos.failure(TestError(true)))
+            .finish()
+            .unwrap();
+        assert_eq!((res_pos.offset, val), (1, 1));
+
+        assert_eq!(pd.take_ambiguities(), Some(Vec::new()));
+    }
 }
@@ -0,0 +1,202 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::error_accumulator::Expectation;
+use crate::{DriverError, ErrorKind, ParseDriver, Pos, Progress, Recoverable};
+
+/// Wraps the specified `parser`, labelling it for error reporting.
+///
+/// While `parser` runs, `label` is pushed onto the context stack of the
+/// [`ParseDriver`](crate::ParseDriver) (see
+/// [`context_stack`](crate::ParseDriver::context_stack)). If `parser` fails, its error is
+/// wrapped into a [`Contextual`](Contextual) carrying the whole label chain at the point of
+/// failure. Errors that already carry a context (from a nested `context`) are passed through.
+///
+/// Note: If `parser` already returns a `Contextual`, the error type of the result might
+/// have to be annotated, as it can't be inferred in that case.
+#[inline]
+pub fn context<P, T, E, E2, F, S>(
+    label: &'static str,
+    parser: F,
+) -> impl FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T, Contextual<E2>>
+where
+    P: Pos,
+    E: Into<Contextual<E2>>,
+    F: FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
+{
+    move |pd, pos| {
+        pd.context.push(ContextFrame {
+            label,
+            offset: pos.offset(),
+        });
+
+        let progress = parser(pd, pos).map_err(|e| {
+            let mut contextual = e.into();
+            if contextual.frames.is_empty() {
+                // innermost failure, the stack holds the whole chain
+                contextual.frames = pd.context.clone();
+            }
+            contextual
+        });
+
+        pd.context.pop();
+        progress
+    }
+}
+
+/// A single entry of the context stack, see [`context`](context).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ContextFrame {
+    /// The label of the context
+    pub label: &'static str,
+    /// The offset at which the context was entered
+    pub offset: usize,
+}
+
+/// An error, together with the context chain that led to it. See [`context`](context).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contextual<E> {
+    /// The wrapped error
+    pub error: E,
+    /// The context chain at the point of failure, outermost context first
+    pub frames: Vec<ContextFrame>,
+}
+
+impl<E> From<E> for Contextual<E> {
+    /// Wraps the error, without any context.
+    #[inline]
+    fn from(error: E) -> Self {
+        Self {
+            error,
+            frames: Vec::new(),
+        }
+    }
+}
+
+impl<E: Recoverable> Recoverable for Contextual<E> {
+    #[inline]
+    fn kind(&self) -> ErrorKind {
+        self.error.kind()
+    }
+
+    #[inline]
+    fn as_expectation(&self) -> Option<&dyn Expectation> {
+        self.error.as_expectation()
+    }
+
+    /// Wraps the converted error, without any context.
+    #[inline]
+    fn from_driver_error(err: DriverError) -> Self {
+        E::from_driver_error(err).into()
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for Contextual<E> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        if self.frames.is_empty() {
+            return Ok(());
+        }
+
+        f.write_str(" (")?;
+        for (i, frame) in self.frames.iter().rev().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "in {} at offset {}", frame.label, frame.offset)?;
+        }
+        f.write_str(")")
+    }
+}
+
+impl<E: Error + 'static> Error for Contextual<E> {
+    #[inline]
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::slice::num::u8_le;
+    use crate::slice::{BytePos, NotEnoughDataError};
+    use crate::testing::fixtures::TestError;
+    use crate::{ParseDriver, Progress};
+
+    use super::*;
+
+    type Pos<'a> = BytePos<'a>;
+
+    fn array<'a>(
+        pd: &mut ParseDriver,
+        pos: Pos<'a>,
+    ) -> Progress<Pos<'a>, Vec<u8>, Contextual<NotEnoughDataError>> {
+        let (mut pos, len) = pahs!(context("array length", u8_le)(pd, pos));
+
+        let mut elems = Vec::new();
+        for _ in 0..len {
+            let (new_pos, elem) = pahs!(context("array element", u8_le)(pd, pos));
+            assert_eq!(pd.context_stack().len(), 1);
+            elems.push(elem);
+            pos = new_pos;
+        }
+
+        Progress::success(pos, elems)
+    }
+
+    #[test]
+    fn it_records_the_context_chain() {
+        let pd = &mut ParseDriver::new();
+        let input = &[0xFF, 3, 1, 2];
+        let pos = BytePos::new(input).advance_by(1);
+
+        let progress: Progress<_, _, Contextual<NotEnoughDataError>> =
+            pd.context("array", pos, array);
+        let (_, err) = progress.unwrap_err();
+        assert_eq!(
+            err.frames,
+            &[
+                ContextFrame {
+                    label: "array",
+                    offset: 1
+                },
+                ContextFrame {
+                    label: "array element",
+                    offset: 4
+                }
+            ]
+        );
+        assert_eq!(
+            err.to_string(),
+            "not enough data at offset 4: needed 1, but only 0 left \
+             (in array element at offset 4, in array at offset 1)"
+        );
+        assert!(pd.context_stack().is_empty());
+
+        let input = &[3, 1, 2, 3];
+        let progress: Progress<_, _, Contextual<NotEnoughDataError>> =
+            pd.context("array", BytePos::new(input), array);
+        let (new_pos, elems) = progress.unwrap();
+        assert_eq!(new_pos.offset, 4);
+        assert_eq!(elems, &[1, 2, 3]);
+        assert!(pd.context_stack().is_empty());
+    }
+
+    #[test]
+    fn it_keeps_the_recoverability_of_the_inner_error() {
+        let pd = &mut ParseDriver::new();
+
+        let (_, err) = context("rule", |_, pos| {
+            Progress::<_, (), _>::failure(pos, TestError(true))
+        })(pd, 0usize)
+        .unwrap_err();
+        assert!(err.recoverable());
+
+        let (_, err) = context("rule", |_, pos| {
+            Progress::<_, (), _>::failure(pos, TestError(false))
+        })(pd, 0usize)
+        .unwrap_err();
+        assert!(!err.recoverable());
+    }
+}
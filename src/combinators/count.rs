@@ -1,4 +1,4 @@
-use crate::{ParseDriver, Pos, Progress, Push};
+use crate::{ParseDriver, Pos, Progress, Push, Recoverable};
 
 /// Runs the specified parser `n` times, returning all parsed values in a `Vec`.
 ///
@@ -16,6 +16,7 @@ pub fn count<P, T, E, F, S>(
 ) -> impl FnOnce(&mut ParseDriver<S>, P) -> Progress<P, Vec<T>, E>
 where
     P: Pos,
+    E: Recoverable,
     F: FnMut(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
 {
     count_push_into(n, move || Vec::with_capacity(n), parser)
@@ -31,6 +32,7 @@ pub fn skip_count<P, T, E, F, S>(
 ) -> impl FnOnce(&mut ParseDriver<S>, P) -> Progress<P, (), E>
 where
     P: Pos,
+    E: Recoverable,
     F: FnMut(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
 {
     count_push_into(n, || (), parser)
@@ -39,7 +41,21 @@ where
 /// Runs the specified parser `n` times, pushing all values into the supplied [`Push`](Push)
 /// value.
 ///
-/// On failure, rewinds the position back to the initial position.
+/// On failure, rewinds the position back to the initial position. As all `n` values are
+/// required, every kind of error fails, including
+/// [`Backtrack`](crate::ErrorKind::Backtrack).
+///
+/// If the parser succeeds without progressing, it fails with
+/// [`DriverError::NoProgress`](crate::DriverError::NoProgress) by default. See
+/// [`NoProgressPolicy`](crate::NoProgressPolicy) for the details and alternatives. As the
+/// number of repetitions is bounded, it continues if the repetition isn't failed.
+///
+/// If the parse is cancelled (see
+/// [`ParseDriver::set_cancellation_token`](ParseDriver::set_cancellation_token)), it fails
+/// with [`DriverError::Cancelled`](crate::DriverError::Cancelled) before the next repetition.
+/// Every repetition is charged to the budget (see
+/// [`ParseDriver::set_budget`](ParseDriver::set_budget)), failing with
+/// [`DriverError::BudgetExhausted`](crate::DriverError::BudgetExhausted) once it's exhausted.
 #[inline]
 pub fn count_push_into<P, T, E, Fp, S, C, Fc>(
     n: usize,
@@ -48,6 +64,7 @@ pub fn count_push_into<P, T, E, Fp, S, C, Fc>(
 ) -> impl FnOnce(&mut ParseDriver<S>, P) -> Progress<P, C, E>
 where
     P: Pos,
+    E: Recoverable,
     Fp: FnMut(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
     C: Push<T>,
     Fc: FnOnce() -> C,
@@ -57,12 +74,20 @@ where
         let orig_pos = curr_pos;
 
         for _ in 0..n {
-            match parser(pd, curr_pos) {
+            if let Some(err) = pd.cancelled(curr_pos) {
+                return Progress::failure(orig_pos, err);
+            }
+
+            match pd.budgeted(curr_pos, &mut parser) {
                 Progress {
                     status: Ok(val),
                     pos: new_pos,
                 } => {
-                    opt_assert!(new_pos != curr_pos, "parser did not progress");
+                    if new_pos == curr_pos {
+                        if let Some(err) = pd.no_progress("count", curr_pos) {
+                            return Progress::failure(orig_pos, err);
+                        }
+                    }
 
                     coll.push(val);
                     curr_pos = new_pos;
@@ -82,22 +107,58 @@ where
 mod test {
     use crate::slice::num::u8_le;
     use crate::slice::BytePos;
-    use crate::ParseDriver;
+    use crate::{
+        CancellationToken, DriverError, ErrorKind, NoProgressPolicy, ParseDriver, Progress,
+        Recoverable,
+    };
 
     use super::{count, skip_count};
 
+    #[derive(Debug, PartialEq)]
+    enum Error {
+        NotEnoughData,
+        Cancelled(usize),
+        NoProgress(usize, &'static str),
+        Driver(DriverError),
+    }
+
+    impl Recoverable for Error {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                Error::NotEnoughData => ErrorKind::Backtrack,
+                Error::Cancelled(..) | Error::NoProgress(..) | Error::Driver(..) => {
+                    ErrorKind::Fatal
+                }
+            }
+        }
+
+        fn from_driver_error(err: DriverError) -> Self {
+            match err {
+                DriverError::Cancelled { offset } => Error::Cancelled(offset),
+                DriverError::NoProgress { offset, combinator } => {
+                    Error::NoProgress(offset, combinator)
+                }
+                err => Error::Driver(err),
+            }
+        }
+    }
+
+    fn byte<'a>(pd: &mut ParseDriver, pos: BytePos<'a>) -> Progress<BytePos<'a>, u8, Error> {
+        u8_le(pd, pos).map_err(|_| Error::NotEnoughData)
+    }
+
     #[test]
     fn it_works() {
         let input = &[0u8, 1, 2, 3, 4, 5, 6, 7, 8];
         let pos = BytePos::new(input);
         let pd = &mut ParseDriver::new();
 
-        let (new_pos, vec) = count(6, u8_le)(pd, pos).unwrap();
+        let (new_pos, vec) = count(6, byte)(pd, pos).unwrap();
         assert_eq!(new_pos.offset, 6);
         assert_eq!(new_pos.s, &input[6..]);
         assert_eq!(vec, &[0u8, 1, 2, 3, 4, 5]);
 
-        let (new_pos, _) = skip_count(6, u8_le)(pd, pos).unwrap();
+        let (new_pos, _) = skip_count(6, byte)(pd, pos).unwrap();
         assert_eq!(new_pos.offset, 6);
         assert_eq!(new_pos.s, &input[6..]);
     }
@@ -108,10 +169,55 @@ mod test {
         let pos = BytePos::new(input);
         let pd = &mut ParseDriver::new();
 
-        let (new_pos, _) = count(10, u8_le)(pd, pos).unwrap_err();
+        let (new_pos, _) = count(10, byte)(pd, pos).unwrap_err();
         assert_eq!(new_pos.offset, 0);
 
-        let (new_pos, _) = skip_count(10, u8_le)(pd, pos).unwrap_err();
+        let (new_pos, _) = skip_count(10, byte)(pd, pos).unwrap_err();
         assert_eq!(new_pos.offset, 0);
     }
+
+    #[test]
+    fn it_stops_when_cancelled() {
+        let input = &[0u8, 1, 2, 3, 4, 5, 6, 7, 8];
+        let pos = BytePos::new(input);
+        let pd = &mut ParseDriver::new();
+        let token = CancellationToken::new();
+        pd.set_cancellation_token(token.clone());
+
+        let (new_pos, err) = count(6, |pd, pos| -> Progress<_, _, Error> {
+            let (pos, b) = pahs!(byte(pd, pos));
+            if b == 2 {
+                token.cancel();
+            }
+            Progress::success(pos, b)
+        })(pd, pos)
+        .unwrap_err();
+        assert_eq!(new_pos.offset, 0);
+        assert_eq!(err, Error::Cancelled(3));
+    }
+
+    #[test]
+    fn it_handles_parsers_that_do_not_progress() {
+        let input = &[0u8, 1, 2, 3];
+        let pos = BytePos::new(input);
+        let pd = &mut ParseDriver::new();
+
+        // gets stuck at offset 1
+        let stuck = |pd: &mut ParseDriver, pos: BytePos<'static>| {
+            if pos.offset == 1 {
+                pos.success(0)
+            } else {
+                byte(pd, pos)
+            }
+        };
+
+        let (new_pos, err) = count(3, stuck)(pd, pos).unwrap_err();
+        assert_eq!(new_pos.offset, 0);
+        assert_eq!(err, Error::NoProgress(1, "count"));
+
+        pd.set_no_progress_policy(NoProgressPolicy::Ignore);
+        let (new_pos, vec) = count(3, stuck)(pd, pos).unwrap();
+        assert_eq!(new_pos.offset, 1);
+        assert_eq!(vec, &[0, 0, 0]);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{ParseDriver, Pos, Progress, Push, Recoverable};
+use crate::{ErrorKind, ParseDriver, Pos, Progress, Push, Recoverable};
 
 /// Runs the specified parser until it stops matching (but at least once),
 /// collecting all values into a Vec.
@@ -23,6 +23,18 @@ where
 /// collecting all values into the supplied [`Push`](Push) value.
 ///
 /// Needs to run at least once to succeed.
+///
+/// Only errors of kind [`Backtrack`](crate::ErrorKind::Backtrack) end the repetition
+/// successfully. Any other kind fails, rewinding back to the initial position: an
+/// [`Incomplete`](crate::ErrorKind::Incomplete) input might continue with more elements.
+///
+/// If the parser succeeds without progressing, it fails with
+/// [`DriverError::NoProgress`](crate::DriverError::NoProgress) by default. See
+/// [`NoProgressPolicy`](crate::NoProgressPolicy) for the details and alternatives. If the
+/// repetition isn't failed, it stops successfully.
+///
+/// The failure ending the repetition is recorded via
+/// [`ParseDriver::record_failure`](ParseDriver::record_failure).
 #[inline]
 pub fn one_or_more_push_into<P, T, E, Fp, S, C, Fc>(
     build_push: Fc,
@@ -38,28 +50,50 @@ where
     move |pd, start_pos| {
         let mut coll = build_push();
 
-        let (pos_after_first, val) = pahs!(parser(pd, start_pos));
-        opt_assert!(pos_after_first != start_pos, "parser did not progress");
+        let (pos_after_first, val) = pahs!(iteration(pd, start_pos, 0, &mut parser));
+        if pos_after_first == start_pos {
+            if let Some(err) = pd.no_progress("one_or_more", start_pos) {
+                return Progress::failure(start_pos, err);
+            }
+        }
         coll.push(val);
 
         let mut curr_pos = pos_after_first;
+        let mut i = 1;
         loop {
-            match parser(pd, curr_pos) {
+            match iteration(pd, curr_pos, i, &mut parser) {
+                Progress {
+                    pos,
+                    status: Ok(..),
+                } if pos == curr_pos => {
+                    return match pd.no_progress("one_or_more", pos) {
+                        Some(err) => Progress::failure(start_pos, err),
+                        None => Progress::success(curr_pos, coll),
+                    };
+                }
+
                 Progress {
                     pos,
                     status: Ok(val),
                 } => {
-                    opt_assert!(curr_pos != pos, "parser did not progress");
-
                     coll.push(val);
                     curr_pos = pos;
+                    i += 1;
                 }
 
                 Progress {
                     status: Err(err), ..
-                } if !err.recoverable() => return Progress::failure(start_pos, err),
+                } if err.kind() != ErrorKind::Backtrack => {
+                    return Progress::failure(start_pos, err)
+                }
 
-                _err => return Progress::success(curr_pos, coll),
+                Progress {
+                    pos,
+                    status: Err(err),
+                } => {
+                    pd.record_failure(pos, &err);
+                    return Progress::success(curr_pos, coll);
+                }
             }
         }
     }
@@ -84,6 +118,18 @@ where
 
 /// Runs the specified parser until it stops matching,
 /// collecting all values into the supplied [`Push`](Push) value.
+///
+/// Only errors of kind [`Backtrack`](crate::ErrorKind::Backtrack) end the repetition
+/// successfully. Any other kind fails, rewinding back to the initial position: an
+/// [`Incomplete`](crate::ErrorKind::Incomplete) input might continue with more elements.
+///
+/// If the parser succeeds without progressing, it fails with
+/// [`DriverError::NoProgress`](crate::DriverError::NoProgress) by default. See
+/// [`NoProgressPolicy`](crate::NoProgressPolicy) for the details and alternatives. If the
+/// repetition isn't failed, it stops successfully.
+///
+/// The failure ending the repetition is recorded via
+/// [`ParseDriver::record_failure`](ParseDriver::record_failure).
 #[inline]
 pub fn zero_or_more_push_into<P, T, E, Fp, S, C, Fc>(
     build_push: Fc,
@@ -100,33 +146,76 @@ where
         let mut coll = build_push();
 
         let mut curr_pos = start_pos;
+        let mut i = 0;
         loop {
-            match parser(pd, curr_pos) {
+            match iteration(pd, curr_pos, i, &mut parser) {
+                Progress {
+                    pos,
+                    status: Ok(..),
+                } if pos == curr_pos => {
+                    return match pd.no_progress("zero_or_more", pos) {
+                        Some(err) => Progress::failure(start_pos, err),
+                        None => Progress::success(curr_pos, coll),
+                    };
+                }
+
                 Progress {
                     pos,
                     status: Ok(val),
                 } => {
-                    opt_assert!(curr_pos != pos, "parser did not progress");
-
                     coll.push(val);
                     curr_pos = pos;
+                    i += 1;
                 }
 
                 Progress {
                     status: Err(err), ..
-                } if !err.recoverable() => return Progress::failure(start_pos, err),
+                } if err.kind() != ErrorKind::Backtrack => {
+                    return Progress::failure(start_pos, err)
+                }
 
-                _err => return Progress::success(curr_pos, coll),
+                Progress {
+                    pos,
+                    status: Err(err),
+                } => {
+                    pd.record_failure(pos, &err);
+                    return Progress::success(curr_pos, coll);
+                }
             }
         }
     }
 }
 
+/// Runs the `i`th iteration of a repetition, recording it in the trace if enabled
+/// and charging it to the budget.
+///
+/// Fails without running `parser` if the parse was cancelled.
+fn iteration<P, T, E, F, S>(
+    pd: &mut ParseDriver<S>,
+    pos: P,
+    i: usize,
+    parser: &mut F,
+) -> Progress<P, T, E>
+where
+    P: Pos,
+    E: Recoverable,
+    F: FnMut(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
+{
+    if let Some(err) = pd.cancelled(pos) {
+        return Progress::failure(pos, err);
+    }
+
+    let id = pd.trace_enter(|| format!("iteration {}", i).into(), pos);
+    let progress = pd.budgeted(pos, parser);
+    pd.trace_exit(id, &progress, |_| None);
+    progress
+}
+
 #[cfg(test)]
 mod test {
     use crate::slice::num::u8_le;
-    use crate::slice::BytePos;
-    use crate::{ParseDriver, Progress, Recoverable};
+    use crate::slice::{tag, BytePos};
+    use crate::{DriverError, ErrorKind, NoProgressPolicy, ParseDriver, Progress, Recoverable};
 
     use super::{one_or_more, zero_or_more};
 
@@ -134,17 +223,38 @@ mod test {
     enum Error {
         NotEnoughData,
         TooBig,
+        Incomplete,
+        NoProgress(usize),
+        Driver(DriverError),
     }
 
     impl Recoverable for Error {
-        fn recoverable(&self) -> bool {
+        fn kind(&self) -> ErrorKind {
             match self {
-                Error::NotEnoughData => true,
-                Error::TooBig => false,
+                Error::NotEnoughData => ErrorKind::Backtrack,
+                Error::TooBig | Error::NoProgress(..) => ErrorKind::Cut,
+                Error::Driver(..) => ErrorKind::Fatal,
+                Error::Incomplete => ErrorKind::Incomplete,
+            }
+        }
+
+        fn from_driver_error(err: DriverError) -> Self {
+            match err {
+                DriverError::NoProgress { offset, .. } => Error::NoProgress(offset),
+                err => Error::Driver(err),
             }
         }
     }
 
+    /// Parses bytes until offset 2, then succeeds without progressing.
+    fn stuck_parser<'a, E>(pd: &mut ParseDriver, pos: BytePos<'a>) -> Progress<BytePos<'a>, u8, E> {
+        if pos.offset < 2 {
+            u8_le(pd, pos).map_err(|_| unreachable!())
+        } else {
+            pos.success(0)
+        }
+    }
+
     fn under_64_parser<'a>(
         pd: &mut ParseDriver,
         pos: BytePos<'a>,
@@ -215,4 +325,87 @@ mod test {
         assert_eq!(new_pos.offset, 0);
         assert_eq!(err, Error::TooBig);
     }
+
+    #[test]
+    fn incomplete_input_fails_the_repetition() {
+        let input = &[0u8, 1, 2];
+        let pos = BytePos::new(input);
+        let pd = &mut ParseDriver::new();
+
+        let (new_pos, err) = zero_or_more(|pd, pos| {
+            under_64_parser(pd, pos).map_err(|e| match e {
+                Error::NotEnoughData => Error::Incomplete,
+                e => e,
+            })
+        })(pd, pos)
+        .unwrap_err();
+        assert_eq!(new_pos.offset, 0);
+        assert_eq!(err, Error::Incomplete);
+    }
+
+    #[test]
+    fn repetitions_fail_if_the_parser_does_not_progress() {
+        let input = &[0u8, 1, 2, 3];
+        let pos = BytePos::new(input);
+        let pd = &mut ParseDriver::new();
+
+        let (new_pos, err) = zero_or_more(stuck_parser::<Error>)(pd, pos).unwrap_err();
+        assert_eq!(new_pos.offset, 0);
+        assert_eq!(err, Error::NoProgress(2));
+
+        let (new_pos, err) = one_or_more(stuck_parser::<Error>)(pd, pos.advance_by(2)).unwrap_err();
+        assert_eq!(new_pos.offset, 2);
+        assert_eq!(err, Error::NoProgress(2));
+    }
+
+    #[test]
+    fn repetitions_of_empty_tags_fail_by_default() {
+        let pd = &mut ParseDriver::new();
+        let empty =
+            |pd: &mut ParseDriver, pos| tag(b"")(pd, pos).map_err(|_| -> Error { unreachable!() });
+
+        let (new_pos, err) = zero_or_more(empty)(pd, BytePos::new(b"abc")).unwrap_err();
+        assert_eq!(new_pos.offset, 0);
+        assert_eq!(err, Error::NoProgress(0));
+        assert!(!err.recoverable());
+    }
+
+    #[test]
+    fn repetitions_stop_on_no_progress_if_ignored() {
+        let input = &[0u8, 1, 2, 3];
+        let pos = BytePos::new(input);
+        let pd = &mut ParseDriver::new();
+        pd.set_no_progress_policy(NoProgressPolicy::Ignore);
+        assert_eq!(pd.no_progress_policy(), NoProgressPolicy::Ignore);
+
+        let (new_pos, vec) = zero_or_more(stuck_parser::<Error>)(pd, pos).unwrap();
+        assert_eq!(new_pos.offset, 2);
+        assert_eq!(vec, &[0, 1]);
+
+        let (new_pos, vec) = one_or_more(stuck_parser::<Error>)(pd, pos.advance_by(2)).unwrap();
+        assert_eq!(new_pos.offset, 2);
+        assert_eq!(vec, &[0]);
+    }
+
+    #[cfg(not(feature = "panic_free"))]
+    #[test]
+    #[should_panic(expected = "parser did not progress in one_or_more at offset 2")]
+    fn repetitions_panic_on_no_progress_if_configured() {
+        let input = &[0u8, 1, 2, 3];
+        let pd = &mut ParseDriver::new();
+        pd.set_no_progress_policy(NoProgressPolicy::Panic);
+
+        let _ = one_or_more(stuck_parser::<Error>)(pd, BytePos::new(input));
+    }
+
+    #[cfg(feature = "panic_free")]
+    #[test]
+    fn it_fails_on_no_progress_with_panic_free() {
+        let input = &[0u8, 1, 2, 3];
+        let pd = &mut ParseDriver::new();
+        pd.set_no_progress_policy(NoProgressPolicy::Panic);
+
+        let (_, err) = one_or_more(stuck_parser::<Error>)(pd, BytePos::new(input)).unwrap_err();
+        assert_eq!(err, Error::NoProgress(2));
+    }
 }
@@ -0,0 +1,153 @@
+use crate::{DriverError, ParseDriver, Pos, Progress, Recoverable};
+
+/// Wraps the specified `parser`, marking it as one level deeper in a recursive grammar.
+///
+/// While `parser` runs, the [`depth`](ParseDriver::depth) of the driver is increased by one.
+/// If that would exceed the maximum depth set via
+/// [`ParseDriver::set_max_depth`](ParseDriver::set_max_depth), `parser` isn't run, and it
+/// fails with [`DriverError::DepthLimitExceeded`](crate::DriverError::DepthLimitExceeded)
+/// instead, converted via
+/// [`Recoverable::from_driver_error`](crate::Recoverable::from_driver_error).
+///
+/// `parser` is charged to the budget of the driver, see
+/// [`ParseDriver::set_budget`](ParseDriver::set_budget).
+///
+/// Wrap the recursive parts of a grammar, like the elements of an array, to protect against
+/// stack overflows on deeply nested input.
+#[inline]
+pub fn nested<P, T, E, F, S>(parser: F) -> impl FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T, E>
+where
+    P: Pos,
+    E: Recoverable,
+    F: FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
+{
+    move |pd, pos| {
+        if let Some(max_depth) = pd.max_depth() {
+            if pd.depth() >= max_depth {
+                let err = DriverError::DepthLimitExceeded {
+                    offset: pos.offset(),
+                    max_depth,
+                };
+                return Progress::failure(pos, E::from_driver_error(err));
+            }
+        }
+
+        pd.depth += 1;
+        let progress = pd.budgeted(pos, parser);
+        pd.depth -= 1;
+        progress
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::combinators::zero_or_more;
+    use crate::slice::BytePos;
+    use crate::testing::fixtures::{byte, Error};
+    use crate::{Budget, DriverError, ErrorKind, Located, ParseDriver, Progress, Recoverable};
+
+    /// `list = "[" items`, returning the maximum depth
+    fn list<'a>(pd: &mut ParseDriver, pos: BytePos<'a>) -> Progress<BytePos<'a>, usize, Error> {
+        let (pos, _) = pahs!(byte(b'[')(pd, pos));
+        items(pd, pos)
+    }
+
+    /// `items = ("[" items)* "]"`, with every nested `items` one level deeper
+    fn items<'a>(pd: &mut ParseDriver, pos: BytePos<'a>) -> Progress<BytePos<'a>, usize, Error> {
+        let depth = pd.depth();
+        let (pos, depths) = pahs!(zero_or_more(|pd, pos| {
+            let (pos, _) = pahs!(byte(b'[')(pd, pos));
+            pd.nested(pos, items)
+        })(pd, pos));
+        let (pos, _) = pahs!(byte(b']')(pd, pos));
+        Progress::success(pos, depths.into_iter().max().unwrap_or(depth))
+    }
+
+    #[test]
+    fn it_tracks_the_depth() {
+        let pd = &mut ParseDriver::new();
+
+        let (pos, depth) = list(pd, BytePos::new(b"[[][[[]]]]")).unwrap();
+        assert_eq!((pos.offset, depth), (10, 3));
+        assert_eq!(pd.depth(), 0);
+    }
+
+    #[test]
+    fn it_limits_the_depth() {
+        let pd = &mut ParseDriver::new();
+        pd.set_max_depth(2);
+        assert_eq!(pd.max_depth(), Some(2));
+
+        let (_, depth) = list(pd, BytePos::new(b"[[[]][]]")).unwrap();
+        assert_eq!(depth, 2);
+
+        let (pos, err) = list(pd, BytePos::new(b"[[][[[]]]]")).unwrap_err();
+        assert_eq!(pos.offset, 1);
+        assert_eq!(
+            err,
+            Error::Driver(DriverError::DepthLimitExceeded {
+                offset: 6,
+                max_depth: 2
+            })
+        );
+        assert_eq!(pd.depth(), 0);
+
+        pd.clear_max_depth();
+        assert_eq!(pd.max_depth(), None);
+        assert!(list(pd, BytePos::new(b"[[][[[]]]]")).is_ok());
+    }
+
+    #[test]
+    fn it_checks_the_budget() {
+        let pd = &mut ParseDriver::new();
+        pd.set_budget(Budget::Invocations(3));
+
+        // `zero_or_more` and `nested` are both charged, once their invocations have returned
+        let (pos, err) = list(pd, BytePos::new(b"[[[]]]")).unwrap_err();
+        assert_eq!(pos.offset, 1);
+        assert_eq!(
+            err,
+            Error::Driver(DriverError::BudgetExhausted { offset: 4 })
+        );
+        assert_eq!(pd.depth(), 0);
+    }
+
+    #[test]
+    fn it_fails_with_depth_limit_exceeded() {
+        #[derive(Debug, PartialEq)]
+        struct Limit(DriverError);
+
+        impl Recoverable for Limit {
+            fn kind(&self) -> ErrorKind {
+                ErrorKind::Fatal
+            }
+
+            fn from_driver_error(err: DriverError) -> Self {
+                Limit(err)
+            }
+        }
+
+        let pd = &mut ParseDriver::new();
+        pd.set_max_depth(0);
+
+        let (pos, err) = pd
+            .nested(3usize, |_, pos| Progress::<_, (), Limit>::success(pos, ()))
+            .unwrap_err();
+        assert_eq!(pos, 3);
+        assert_eq!(
+            err,
+            Limit(DriverError::DepthLimitExceeded {
+                offset: 3,
+                max_depth: 0
+            })
+        );
+        assert!(!err.recoverable());
+
+        let (_, err) = pd
+            .nested(3usize, |_, pos| {
+                Progress::<_, (), Located<Limit>>::success(pos, ())
+            })
+            .unwrap_err();
+        assert_eq!(err.offset, 3);
+    }
+}
@@ -1,10 +1,15 @@
-use crate::{ParseDriver, Pos, Progress, Recoverable};
+use crate::{ErrorKind, ParseDriver, Pos, Progress, Recoverable};
 
 /// Wraps the specified `parser`, making it optional.
 ///
 /// If `parser` was successful, the value is mapped to `Some(value)`.
 /// Recoverable failures are mapped to successes, with `None` as value.
 /// Irrecoverable failures stay that way.
+///
+/// Only errors of kind [`Backtrack`](crate::ErrorKind::Backtrack) are recoverable, so
+/// for example an [`Incomplete`](crate::ErrorKind::Incomplete) input still fails.
+///
+/// Recovered failures are recorded via [`ParseDriver::record_failure`](ParseDriver::record_failure).
 #[inline]
 pub fn optional<P, T, E, F, S>(
     parser: F,
@@ -17,7 +22,7 @@ where
     move |pd, pos| {
         let orig_pos = pos;
 
-        match parser(pd, pos) {
+        match pd.budgeted(pos, parser) {
             Progress {
                 status: Ok(val),
                 pos,
@@ -26,7 +31,8 @@ where
                 status: Err(e),
                 pos,
             } => {
-                if e.recoverable() {
+                if e.kind() == ErrorKind::Backtrack {
+                    pd.record_failure(pos, &e);
                     Progress::success(orig_pos, None)
                 } else {
                     Progress::failure(pos, e)
@@ -39,19 +45,11 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    #[derive(Debug, PartialEq)]
-    struct TestError(bool);
-
-    impl Recoverable for TestError {
-        fn recoverable(&self) -> bool {
-            self.0
-        }
-    }
+    use crate::testing::fixtures::TestError;
 
     #[test]
     fn successful_progress_gets_passed_through() {
-        let mut pd = ParseDriver { state: () };
+        let mut pd = ParseDriver::new();
         let prog = optional(|_, pos| Progress::<_, _, TestError>::success(pos, "test"))(&mut pd, 0);
 
         // would panic if Progress::status isn't Ok
@@ -60,7 +58,7 @@ mod tests {
 
     #[test]
     fn recoverable_errors_turn_into_success_none() {
-        let mut pd = ParseDriver { state: () };
+        let mut pd = ParseDriver::new();
         let prog =
             optional(|_, pos| Progress::<_, (), _>::failure(pos, TestError(true)))(&mut pd, 0);
 
@@ -70,7 +68,7 @@ mod tests {
 
     #[test]
     fn irrecoverable_errors_stay_failed() {
-        let mut pd = ParseDriver { state: () };
+        let mut pd = ParseDriver::new();
         let prog =
             optional(|_, pos| Progress::<_, (), _>::failure(pos, TestError(false)))(&mut pd, 0);
 
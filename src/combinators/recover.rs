@@ -0,0 +1,206 @@
+use crate::{ErrorKind, ParseDriver, Pos, Progress, Recoverable};
+
+/// Wraps the specified `parser`, recovering from malformed input by skipping to a
+/// synchronization point.
+///
+/// If `parser` was successful, the value is mapped to [`Recovered::Parsed`](Recovered::Parsed).
+///
+/// If `parser` fails with an error of kind [`Cut`](crate::ErrorKind::Cut), `sync` is run from
+/// the initial position to skip the malformed input, for example up to the next `;`. If `sync`
+/// succeeds, so does this parser, returning the error as
+/// [`Recovered::Skipped`](Recovered::Skipped). If it doesn't, the original error is returned.
+///
+/// Both `parser` and `sync` are charged to the budget of the driver as a single invocation, see
+/// [`ParseDriver::set_budget`](crate::ParseDriver::set_budget).
+///
+/// All other kinds of errors are passed on, so that for example a repetition can still
+/// end on [`Backtrack`](crate::ErrorKind::Backtrack) errors. Repetitions collect the recovered
+/// errors along with the parsed values, see [`Recovered::partition`](Recovered::partition).
+#[inline]
+pub fn recover_with<P, T, E, F, T2, E2, Fs, S>(
+    parser: F,
+    sync: Fs,
+) -> impl FnOnce(&mut ParseDriver<S>, P) -> Progress<P, Recovered<T, E>, E>
+where
+    P: Pos,
+    E: Recoverable,
+    F: FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
+    Fs: FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T2, E2>,
+{
+    move |pd, start_pos| {
+        pd.budgeted(start_pos, |pd, start_pos| match parser(pd, start_pos) {
+            Progress {
+                status: Ok(val),
+                pos,
+            } => Progress::success(pos, Recovered::Parsed(val)),
+
+            Progress {
+                status: Err(err),
+                pos,
+            } if err.kind() == ErrorKind::Cut => match sync(pd, start_pos) {
+                Progress {
+                    status: Ok(..),
+                    pos: sync_pos,
+                } => Progress::success(
+                    sync_pos,
+                    Recovered::Skipped(RecoveredError {
+                        offset: pos.offset(),
+                        error: err,
+                    }),
+                ),
+                Progress {
+                    status: Err(..), ..
+                } => Progress::failure(pos, err),
+            },
+
+            Progress {
+                status: Err(err),
+                pos,
+            } => Progress::failure(pos, err),
+        })
+    }
+}
+
+/// The value of [`recover_with`](recover_with).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recovered<T, E> {
+    /// The parser was successful.
+    Parsed(T),
+    /// The parser failed, and the malformed input was skipped.
+    Skipped(RecoveredError<E>),
+}
+
+impl<T, E> Recovered<T, E> {
+    /// Returns the parsed value, if any.
+    #[inline]
+    pub fn parsed(self) -> Option<T> {
+        match self {
+            Recovered::Parsed(val) => Some(val),
+            Recovered::Skipped(..) => None,
+        }
+    }
+
+    /// Returns the error that was recovered from, if any.
+    #[inline]
+    pub fn skipped(self) -> Option<RecoveredError<E>> {
+        match self {
+            Recovered::Parsed(..) => None,
+            Recovered::Skipped(err) => Some(err),
+        }
+    }
+
+    /// Splits the results of repeated [`recover_with`](recover_with)s into the parsed values
+    /// and the errors that were recovered from, both in input order.
+    #[inline]
+    pub fn partition(results: impl IntoIterator<Item = Self>) -> (Vec<T>, Vec<RecoveredError<E>>) {
+        let mut parsed = Vec::new();
+        let mut skipped = Vec::new();
+        for result in results {
+            match result {
+                Recovered::Parsed(val) => parsed.push(val),
+                Recovered::Skipped(err) => skipped.push(err),
+            }
+        }
+        (parsed, skipped)
+    }
+}
+
+/// An error that was recovered from, see [`recover_with`](recover_with).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveredError<E> {
+    /// The offset at which the error happened
+    pub offset: usize,
+    /// The error
+    pub error: E,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::combinators::zero_or_more;
+    use crate::slice::BytePos;
+    use crate::{DriverError, ErrorKind, ParseDriver, Progress, Recoverable};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Error {
+        NoStatement,
+        InvalidStatement,
+        Fatal,
+    }
+
+    impl Recoverable for Error {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                Error::NoStatement => ErrorKind::Backtrack,
+                Error::InvalidStatement => ErrorKind::Cut,
+                Error::Fatal => ErrorKind::Fatal,
+            }
+        }
+
+        fn from_driver_error(_err: DriverError) -> Self {
+            Error::Fatal
+        }
+    }
+
+    /// Parses a single digit, followed by a `;`
+    fn statement<'a>(_: &mut ParseDriver, pos: BytePos<'a>) -> Progress<BytePos<'a>, u8, Error> {
+        match pos.s {
+            [] => pos.failure(Error::NoStatement),
+            [b'!', ..] => pos.failure(Error::Fatal),
+            [d @ b'0'..=b'9', b';', ..] => pos.advance_by(2).success(d - b'0'),
+            _ => pos.advance_by(1).failure(Error::InvalidStatement),
+        }
+    }
+
+    /// Skips past the next `;`
+    fn skip_statement<'a>(_: &mut ParseDriver, pos: BytePos<'a>) -> Progress<BytePos<'a>, (), ()> {
+        match pos.s.iter().position(|&b| b == b';') {
+            Some(i) => pos.advance_by(i + 1).success(()),
+            None => pos.failure(()),
+        }
+    }
+
+    #[test]
+    fn it_collects_partial_results_and_errors() {
+        let input = b"1;x;2;34;5;";
+        let pd = &mut ParseDriver::new();
+
+        let (pos, results) =
+            zero_or_more(|pd, pos| recover_with(statement, skip_statement)(pd, pos))(
+                pd,
+                BytePos::new(input),
+            )
+            .unwrap();
+        assert_eq!(pos.offset, 11);
+
+        let (statements, errors) = Recovered::partition(results);
+        assert_eq!(statements, &[1, 2, 5]);
+        assert_eq!(
+            errors,
+            &[
+                RecoveredError {
+                    offset: 3,
+                    error: Error::InvalidStatement
+                },
+                RecoveredError {
+                    offset: 7,
+                    error: Error::InvalidStatement
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn it_fails_if_it_cannot_resynchronize() {
+        let pd = &mut ParseDriver::new();
+
+        let (pos, err) =
+            recover_with(statement, skip_statement)(pd, BytePos::new(b"xx")).unwrap_err();
+        assert_eq!((pos.offset, err), (1, Error::InvalidStatement));
+
+        let (_, err) =
+            recover_with(statement, skip_statement)(pd, BytePos::new(b"!;")).unwrap_err();
+        assert_eq!(err, Error::Fatal);
+    }
+}
@@ -0,0 +1,263 @@
+use std::fmt::Debug;
+
+use crate::{ParseDriver, Pos, Progress};
+
+/// Wraps the specified `parser`, naming it for tracing and profiling.
+///
+/// If tracing is enabled (see [`ParseDriver::enable_trace`](ParseDriver::enable_trace)),
+/// every invocation is recorded in the trace with its start and end position, and
+/// the `Debug` output of its error on failure. If profiling is enabled (see
+/// [`ParseDriver::enable_profiling`](ParseDriver::enable_profiling)), every invocation
+/// is counted and timed. Otherwise, `parser` is just run.
+///
+/// With the `tracing` feature enabled, every invocation also opens a `DEBUG` level
+/// [`tracing`](https://docs.rs/tracing) span named `pahs::rule`, with the fields `rule`
+/// (the name), `offset`, `outcome` (`"ok"` or `"error"`), `consumed` (the number of
+/// elements the parser advanced by) and `error` (the `Debug` output of the error, on failure).
+#[inline]
+pub fn rule<P, T, E, F, S>(
+    name: &'static str,
+    parser: F,
+) -> impl FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T, E>
+where
+    P: Pos,
+    E: Debug,
+    F: FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
+{
+    move |pd, pos| {
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "pahs::rule",
+            rule = name,
+            offset = pos.offset(),
+            outcome = tracing::field::Empty,
+            consumed = tracing::field::Empty,
+            error = tracing::field::Empty,
+        );
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+
+        let id = pd.trace_enter(|| name.into(), pos);
+        let started = pd.profile_start();
+        let progress = parser(pd, pos);
+        pd.profile_end(name, pos, &progress, started);
+        pd.trace_exit(id, &progress, |e| Some(format!("{:?}", e)));
+
+        #[cfg(feature = "tracing")]
+        {
+            let consumed = progress.pos.offset().saturating_sub(pos.offset());
+            span.record("consumed", consumed);
+            match &progress.status {
+                Ok(..) => {
+                    span.record("outcome", "ok");
+                }
+                Err(e) => {
+                    span.record("outcome", "error");
+                    span.record("error", tracing::field::debug(e));
+                }
+            }
+        }
+
+        progress
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::combinators::zero_or_more;
+    use crate::profile::Hotspot;
+    use crate::slice::BytePos;
+    use crate::testing::fixtures::{byte, Error};
+    use crate::{ParseDriver, Progress};
+
+    use super::rule;
+
+    fn list<'a>(pd: &mut ParseDriver, pos: BytePos<'a>) -> Progress<BytePos<'a>, Vec<u8>, Error> {
+        rule(
+            "list",
+            zero_or_more(|pd: &mut ParseDriver, pos| {
+                pd.rule("item", pos, |pd, pos| {
+                    pd.alternate(pos).one(byte(b'a')).one(byte(b'b')).finish()
+                })
+            }),
+        )(pd, pos)
+    }
+
+    #[test]
+    fn it_records_a_trace_tree() {
+        let pd = &mut ParseDriver::new();
+        pd.enable_trace();
+
+        let (_, items) = list(pd, BytePos::new(b"ba")).unwrap();
+        assert_eq!(items, b"ba");
+
+        let trace = pd.take_trace().unwrap();
+        assert_eq!(
+            trace.to_tree(),
+            "list [0..2] ok\n\
+             \x20 iteration 0 [0..1] ok\n\
+             \x20   item [0..1] ok\n\
+             \x20     alternate branch 0 [0..0] failed\n\
+             \x20     alternate branch 1 [0..1] ok\n\
+             \x20 iteration 1 [1..2] ok\n\
+             \x20   item [1..2] ok\n\
+             \x20     alternate branch 0 [1..2] ok\n\
+             \x20 iteration 2 [2..2] failed\n\
+             \x20   item [2..2] failed: Expected('b')\n\
+             \x20     alternate branch 0 [2..2] failed\n\
+             \x20     alternate branch 1 [2..2] failed\n"
+        );
+
+        let json = trace.to_json_lines();
+        let lines = json.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), trace.nodes().len());
+        assert_eq!(
+            lines[0],
+            r#"{"name":"list","depth":0,"start":0,"end":2,"success":true,"error":null}"#
+        );
+        assert_eq!(
+            lines[9],
+            r#"{"name":"item","depth":2,"start":2,"end":2,"success":false,"error":"Expected('b')"}"#
+        );
+    }
+
+    #[test]
+    fn it_records_nothing_by_default() {
+        let pd = &mut ParseDriver::new();
+
+        let _ = list(pd, BytePos::new(b"ab"));
+        assert!(pd.take_trace().is_none());
+        assert!(pd.take_profile().is_none());
+    }
+
+    fn a_then<'a>(
+        c: u8,
+    ) -> impl FnOnce(&mut ParseDriver, BytePos<'a>) -> Progress<BytePos<'a>, u8, Error> {
+        move |pd, pos| {
+            let (pos, _) = pahs!(rule("a", byte(b'a'))(pd, pos));
+            byte(c)(pd, pos)
+        }
+    }
+
+    #[test]
+    fn it_profiles_rules() {
+        let pd = &mut ParseDriver::new();
+        pd.enable_profiling();
+
+        let (_, c) = pd
+            .alternate(BytePos::new(b"ac"))
+            .one(rule("ab", a_then(b'b')))
+            .one(rule("ac", a_then(b'c')))
+            .finish()
+            .unwrap();
+        assert_eq!(c, b'c');
+
+        let profile = pd.take_profile().unwrap();
+        let a = profile.rule("a").unwrap();
+        assert_eq!(a.invocations, 2);
+        assert_eq!(a.successes, 2);
+        assert_eq!(a.failures, 0);
+        assert_eq!(a.consumed, 2);
+
+        let ab = profile.rule("ab").unwrap();
+        assert_eq!((ab.invocations, ab.successes, ab.failures), (1, 0, 1));
+        assert_eq!(ab.consumed, 0);
+        let ac = profile.rule("ac").unwrap();
+        assert_eq!((ac.invocations, ac.successes, ac.failures), (1, 1, 0));
+        assert_eq!(ac.consumed, 2);
+        assert!(profile.rule("b").is_none());
+
+        let rules = profile.rules();
+        assert_eq!(rules.len(), 3);
+        assert!(rules.windows(2).all(|w| w[0].1.time >= w[1].1.time));
+
+        let hotspots = profile.hotspots();
+        assert_eq!(
+            hotspots,
+            &[Hotspot {
+                rule: "a",
+                offset: 0,
+                attempts: 2
+            }]
+        );
+
+        let report = profile.report();
+        assert!(report.starts_with("rule "));
+        assert_eq!(report.lines().count(), 1 + 3 + 3);
+        assert!(report.ends_with("a at offset 0: 2 attempts\n"));
+
+        assert!(pd.take_profile().is_none());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn it_opens_tracing_spans() {
+        use std::fmt::Debug;
+        use std::sync::{Arc, Mutex};
+
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        type Fields = Arc<Mutex<Vec<Vec<(String, String)>>>>;
+
+        struct Recorder(Fields);
+
+        struct Visitor<'v>(&'v mut Vec<(String, String)>);
+
+        impl Visit for Visitor<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                self.0
+                    .push((field.name().to_owned(), format!("{:?}", value)));
+            }
+        }
+
+        impl tracing::Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut spans = self.0.lock().unwrap();
+                let mut fields = Vec::new();
+                span.record(&mut Visitor(&mut fields));
+                spans.push(fields);
+                Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, span: &Id, values: &Record<'_>) {
+                let mut spans = self.0.lock().unwrap();
+                values.record(&mut Visitor(&mut spans[span.into_u64() as usize - 1]));
+            }
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, _: &Event<'_>) {}
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let fields = Fields::default();
+        tracing::subscriber::with_default(Recorder(Arc::clone(&fields)), || {
+            let pd = &mut ParseDriver::new();
+            let _ = list(pd, BytePos::new(b"_ab").advance_by(1));
+        });
+
+        let spans = fields.lock().unwrap();
+        let field = |span: &Vec<(String, String)>, name: &str| {
+            span.iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| value.clone())
+                .unwrap()
+        };
+
+        // `list`, and `item` for each of the three iterations
+        assert_eq!(spans.len(), 4);
+        assert_eq!(field(&spans[0], "rule"), "\"list\"");
+        assert_eq!(field(&spans[0], "offset"), "1");
+        assert_eq!(field(&spans[0], "outcome"), "\"ok\"");
+        assert_eq!(field(&spans[0], "consumed"), "2");
+        assert_eq!(field(&spans[3], "rule"), "\"item\"");
+        assert_eq!(field(&spans[3], "outcome"), "\"error\"");
+        assert_eq!(field(&spans[3], "error"), "Expected('b')");
+    }
+}
@@ -0,0 +1,451 @@
+//! Renders compiler-style diagnostics for errors in UTF-8 text input.
+//!
+//! ```text
+//! error: expected `,` or `]`
+//!  --> data.json:2:8
+//!   |
+//! 1 | [
+//!   | - while parsing array
+//! 2 |   1, 2 3
+//!   |        ^ unexpected number
+//! ```
+
+use std::fmt::{self, Write};
+use std::ops::Range;
+
+use crate::combinators::Contextual;
+
+/// A 1-based line and column in a text input.
+///
+/// Columns are counted in characters, not bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineColumn {
+    /// The line, starting at 1
+    pub line: usize,
+    /// The column, starting at 1
+    pub column: usize,
+}
+
+impl LineColumn {
+    /// Computes the line and column of the byte `offset` into `source`.
+    ///
+    /// Offsets past the end of `source` are clamped to its end, offsets inside of
+    /// a multi-byte character refer to that character.
+    #[inline]
+    pub fn of_offset(source: &str, offset: usize) -> Self {
+        let offset = floor_char_boundary(source, offset);
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+
+        Self {
+            line: source[..line_start].matches('\n').count() + 1,
+            column: source[line_start..offset].chars().count() + 1,
+        }
+    }
+
+    /// Computes the line and column of the byte `offset` into the UTF-8 encoded `source`,
+    /// without validating it.
+    ///
+    /// Offsets past the end of `source` are clamped to its end. Invalid UTF-8 sequences
+    /// count as one column per byte that isn't a continuation byte.
+    #[inline]
+    pub fn of_byte_offset(source: &[u8], offset: usize) -> Self {
+        let prefix = &source[..offset.min(source.len())];
+        let line_start = prefix
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+
+        Self {
+            line: prefix[..line_start].iter().filter(|&&b| b == b'\n').count() + 1,
+            column: prefix[line_start..]
+                .iter()
+                .filter(|&&b| b & 0xC0 != 0x80)
+                .count()
+                + 1,
+        }
+    }
+}
+
+impl fmt::Display for LineColumn {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// How a [`Diagnostic`](Diagnostic) is rendered.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Style {
+    /// Plain text.
+    Plain,
+    /// Text with ANSI color escape codes, for terminal output.
+    Ansi,
+}
+
+/// A span of the input, annotated with a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    /// The byte range of the input the label refers to
+    pub span: Range<usize>,
+    /// The message of the label, may be empty
+    pub message: String,
+}
+
+/// A diagnostic message, pointing at the input data that caused it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The main message
+    pub message: String,
+    /// The location the diagnostic is primarily about
+    pub primary: Option<Label>,
+    /// Additional locations relevant to the diagnostic
+    pub secondary: Vec<Label>,
+    /// Notes displayed below the source snippet
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Creates a new diagnostic with the specified message and no labels.
+    #[inline]
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            primary: None,
+            secondary: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    /// Creates a diagnostic for an error that happened at `offset`, adding a secondary
+    /// label for every context that was being parsed at that point.
+    #[inline]
+    pub fn from_contextual<E: fmt::Display>(err: &Contextual<E>, offset: usize) -> Self {
+        err.frames.iter().fold(
+            Self::new(err.error.to_string()).with_primary(offset..offset, ""),
+            |diag, frame| {
+                diag.with_secondary(
+                    frame.offset..frame.offset,
+                    format!("while parsing {}", frame.label),
+                )
+            },
+        )
+    }
+
+    /// Sets the primary label.
+    #[inline]
+    pub fn with_primary(mut self, span: Range<usize>, message: impl Into<String>) -> Self {
+        self.primary = Some(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Adds a secondary label.
+    #[inline]
+    pub fn with_secondary(mut self, span: Range<usize>, message: impl Into<String>) -> Self {
+        self.secondary.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Adds a note.
+    #[inline]
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Renders the diagnostic for the input `source`, which was read from `file_name`.
+    #[inline]
+    pub fn render(&self, file_name: &str, source: &str, style: Style) -> String {
+        let mut out = String::new();
+        // writing to a `String` can't fail
+        self.render_into(&mut out, file_name, source, style)
+            .unwrap();
+        out
+    }
+
+    fn render_into(
+        &self,
+        out: &mut String,
+        file_name: &str,
+        source: &str,
+        style: Style,
+    ) -> fmt::Result {
+        let paint = Painter(style);
+
+        writeln!(
+            out,
+            "{}{}",
+            paint.error("error"),
+            paint.bold(&format!(": {}", self.message))
+        )?;
+
+        let mut annotations = self
+            .primary
+            .iter()
+            .map(|label| Annotation::new(source, label, true))
+            .chain(
+                self.secondary
+                    .iter()
+                    .map(|label| Annotation::new(source, label, false)),
+            )
+            .collect::<Vec<_>>();
+        annotations.sort_by_key(|a| (a.start.line, !a.primary, a.start.column));
+
+        let last_line = annotations.iter().map(|a| a.start.line).max().unwrap_or(0);
+        let width = last_line.to_string().len();
+        let gutter = paint.gutter(&format!("{:w$} |", "", w = width));
+
+        if let Some(primary) = annotations.iter().find(|a| a.primary) {
+            writeln!(
+                out,
+                "{:w$}{} {}:{}",
+                "",
+                paint.gutter("-->"),
+                file_name,
+                primary.start,
+                w = width
+            )?;
+        }
+
+        if !annotations.is_empty() {
+            writeln!(out, "{}", gutter)?;
+        }
+
+        let lines = source.split('\n').collect::<Vec<_>>();
+        let mut prev_line = None;
+        for annotation in &annotations {
+            let line = annotation.start.line;
+            if prev_line != Some(line) {
+                if matches!(prev_line, Some(prev) if line > prev + 1) {
+                    writeln!(out, "{}", paint.gutter("..."))?;
+                }
+
+                let text = lines[line - 1].trim_end_matches('\r');
+                writeln!(
+                    out,
+                    "{} {}",
+                    paint.gutter(&format!("{:w$} |", line, w = width)),
+                    text
+                )?;
+                prev_line = Some(line);
+            }
+
+            // keep tabs, so the underline lines up with the source line
+            let text = lines[line - 1];
+            let indent = text
+                .chars()
+                .take(annotation.start.column - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            let marker = if annotation.primary { "^" } else { "-" }.repeat(annotation.len);
+            let mut underline = format!("{}{}", indent, marker);
+            if !annotation.message.is_empty() {
+                underline.push(' ');
+                underline.push_str(annotation.message);
+            }
+
+            writeln!(
+                out,
+                "{} {}",
+                gutter,
+                paint.marker(&underline, annotation.primary)
+            )?;
+        }
+
+        for note in &self.notes {
+            writeln!(
+                out,
+                "{:w$} {} {}",
+                "",
+                paint.gutter("="),
+                paint.bold(&format!("note: {}", note)),
+                w = width
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A label, resolved to its position in the source.
+struct Annotation<'l> {
+    start: LineColumn,
+    /// The number of characters to underline, at least 1
+    len: usize,
+    message: &'l str,
+    primary: bool,
+}
+
+impl<'l> Annotation<'l> {
+    fn new(source: &str, label: &'l Label, primary: bool) -> Self {
+        let start_offset = floor_char_boundary(source, label.span.start);
+        let end_offset = floor_char_boundary(source, label.span.end.max(start_offset));
+        let start = LineColumn::of_offset(source, start_offset);
+
+        // multi-line spans are only underlined until the end of their first line
+        let spanned = &source[start_offset..end_offset];
+        let spanned = spanned.split('\n').next().unwrap_or("");
+
+        Self {
+            start,
+            len: spanned.chars().count().max(1),
+            message: &label.message,
+            primary,
+        }
+    }
+}
+
+struct Painter(Style);
+
+impl Painter {
+    fn paint(&self, code: &str, text: &str) -> String {
+        match self.0 {
+            Style::Plain => text.to_owned(),
+            Style::Ansi => format!("\x1b[{}m{}\x1b[0m", code, text),
+        }
+    }
+
+    fn error(&self, text: &str) -> String {
+        self.paint("1;31", text)
+    }
+
+    fn bold(&self, text: &str) -> String {
+        self.paint("1", text)
+    }
+
+    fn gutter(&self, text: &str) -> String {
+        self.paint("1;34", text)
+    }
+
+    fn marker(&self, text: &str, primary: bool) -> String {
+        if primary {
+            self.paint("1;31", text)
+        } else {
+            self.paint("1;34", text)
+        }
+    }
+}
+
+fn floor_char_boundary(s: &str, offset: usize) -> usize {
+    let mut offset = offset.min(s.len());
+    while !s.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod test {
+    use crate::combinators::{ContextFrame, Contextual};
+
+    use super::*;
+
+    #[test]
+    fn it_computes_lines_and_columns() {
+        let source = "ab\nc\u{e4}d\n";
+        assert_eq!(
+            LineColumn::of_offset(source, 0),
+            LineColumn { line: 1, column: 1 }
+        );
+        assert_eq!(
+            LineColumn::of_offset(source, 3),
+            LineColumn { line: 2, column: 1 }
+        );
+        // inside and after the multi-byte character
+        assert_eq!(
+            LineColumn::of_offset(source, 5),
+            LineColumn { line: 2, column: 2 }
+        );
+        assert_eq!(
+            LineColumn::of_offset(source, 6),
+            LineColumn { line: 2, column: 3 }
+        );
+        assert_eq!(
+            LineColumn::of_offset(source, 100),
+            LineColumn { line: 3, column: 1 }
+        );
+    }
+
+    #[test]
+    fn it_computes_lines_and_columns_of_bytes() {
+        let source = "ab\nc\u{e4}d\n".as_bytes();
+        for offset in &[0, 3, 6, 100] {
+            let text = std::str::from_utf8(source).unwrap();
+            assert_eq!(
+                LineColumn::of_byte_offset(source, *offset),
+                LineColumn::of_offset(text, *offset)
+            );
+        }
+
+        assert_eq!(
+            LineColumn::of_byte_offset(b"\xff\xfe\nx", 2),
+            LineColumn { line: 1, column: 3 }
+        );
+    }
+
+    #[test]
+    fn it_renders_labels_and_notes() {
+        let source = "[\n  1, 2 3\n]\n";
+        let diag = Diagnostic::new("expected `,` or `]`")
+            .with_primary(9..10, "unexpected number")
+            .with_secondary(0..1, "while parsing array")
+            .with_note("arrays are separated by commas");
+
+        assert_eq!(
+            diag.render("data.json", source, Style::Plain),
+            "error: expected `,` or `]`\n \
+             --> data.json:2:8\n  \
+             |\n\
+             1 | [\n  \
+             | - while parsing array\n\
+             2 |   1, 2 3\n  \
+             |        ^ unexpected number\n  \
+             = note: arrays are separated by commas\n"
+        );
+    }
+
+    #[test]
+    fn it_renders_contextual_errors() {
+        let source = "a = [1, \"x\"]\nb = 2";
+        let err = Contextual {
+            error: "expected number",
+            frames: vec![
+                ContextFrame {
+                    label: "array",
+                    offset: 4,
+                },
+                ContextFrame {
+                    label: "array element",
+                    offset: 8,
+                },
+            ],
+        };
+
+        assert_eq!(
+            Diagnostic::from_contextual(&err, 8).render("<input>", source, Style::Plain),
+            "error: expected number\n \
+             --> <input>:1:9\n  \
+             |\n\
+             1 | a = [1, \"x\"]\n  \
+             |         ^\n  \
+             |     - while parsing array\n  \
+             |         - while parsing array element\n"
+        );
+    }
+
+    #[test]
+    fn it_colors_ansi_output() {
+        let rendered =
+            Diagnostic::new("oops")
+                .with_primary(0..2, "here")
+                .render("f", "abc", Style::Ansi);
+
+        assert!(rendered.starts_with("\x1b[1;31merror\x1b[0m\x1b[1m: oops\x1b[0m\n"));
+        assert!(rendered.contains("\x1b[1;31m^^ here\x1b[0m"));
+    }
+}
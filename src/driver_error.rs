@@ -0,0 +1,80 @@
+use snafu::Snafu;
+
+/// Errors raised by the combinators or the parse driver themselves, instead of by a parser.
+///
+/// Error types represent these via
+/// [`Recoverable::from_driver_error`](crate::Recoverable::from_driver_error).
+/// New variants may be added in the future.
+#[derive(Debug, Clone, Snafu, PartialEq, Eq)]
+#[snafu(visibility = "pub(crate)")]
+#[non_exhaustive]
+pub enum DriverError {
+    /// A repeated parser succeeded without consuming any input, so it would have been
+    /// repeated forever. See [`NoProgressPolicy`](crate::NoProgressPolicy).
+    #[snafu(display("parser did not progress in {} at offset {}", combinator, offset))]
+    NoProgress {
+        /// The offset at which the parser did not progress
+        offset: usize,
+        /// The name of the combinator repeating the parser, like `"zero_or_more"`
+        combinator: &'static str,
+    },
+
+    /// The budget set via [`ParseDriver::set_budget`](crate::ParseDriver::set_budget) ran out.
+    ///
+    /// Error types should treat this as irrecoverable, usually as
+    /// [`ErrorKind::Fatal`](crate::ErrorKind::Fatal).
+    #[snafu(display("parse budget exhausted at offset {}", offset))]
+    BudgetExhausted {
+        /// The offset at which the budget ran out
+        offset: usize,
+    },
+
+    /// The maximum nesting depth set via
+    /// [`ParseDriver::set_max_depth`](crate::ParseDriver::set_max_depth) was exceeded
+    /// by [`nested`](crate::combinators::nested).
+    ///
+    /// Error types should treat this as irrecoverable, usually as
+    /// [`ErrorKind::Fatal`](crate::ErrorKind::Fatal).
+    #[snafu(display("nesting depth limit of {} exceeded at offset {}", max_depth, offset))]
+    DepthLimitExceeded {
+        /// The offset at which the limit was exceeded
+        offset: usize,
+        /// The maximum nesting depth
+        max_depth: usize,
+    },
+
+    /// The parse was cancelled via a [`CancellationToken`](crate::CancellationToken).
+    ///
+    /// Error types should treat this as irrecoverable, usually as
+    /// [`ErrorKind::Fatal`](crate::ErrorKind::Fatal).
+    #[snafu(display("parse cancelled at offset {}", offset))]
+    Cancelled {
+        /// The offset at which the cancellation was noticed
+        offset: usize,
+    },
+
+    /// An [`Alternate`](crate::combinators::Alternate) was finished without trying any
+    /// alternative, which is a bug in the grammar.
+    ///
+    /// Error types should treat this as irrecoverable, usually as
+    /// [`ErrorKind::Fatal`](crate::ErrorKind::Fatal).
+    #[snafu(display("no alternatives were tried at offset {}", offset))]
+    NoAlternatives {
+        /// The offset of the alternation
+        offset: usize,
+    },
+}
+
+impl DriverError {
+    /// Returns the offset at which the error was raised.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        match *self {
+            DriverError::NoProgress { offset, .. }
+            | DriverError::BudgetExhausted { offset }
+            | DriverError::DepthLimitExceeded { offset, .. }
+            | DriverError::Cancelled { offset }
+            | DriverError::NoAlternatives { offset } => offset,
+        }
+    }
+}
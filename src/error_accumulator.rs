@@ -1,6 +1,8 @@
 //! Allows tracking multiple failures encountered in complex parsers or combinators.
 
+use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::fmt;
 
 use crate::{Pos, Progress};
 
@@ -46,7 +48,8 @@ impl<P, E> ErrorAccumulator<P, E> for () {
 
 /// Accumulator that only keeps the last added error.
 ///
-/// Panics if no error was added.
+/// Panics on [`finish`](ErrorAccumulator::finish) if no error was added, see
+/// [`try_finish`](LastErrorOnly::try_finish) for a checked alternative.
 #[derive(Debug)]
 pub struct LastErrorOnly<E> {
     error: Option<E>,
@@ -58,6 +61,12 @@ impl<E> LastErrorOnly<E> {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Consumes the accumulator, returning the last added error, if any.
+    #[inline]
+    pub fn try_finish(self) -> Option<E> {
+        self.error
+    }
 }
 
 impl<E> Default for LastErrorOnly<E> {
@@ -117,6 +126,111 @@ impl<P, E> ErrorAccumulator<P, E> for AllErrorsAccumulator<E> {
     }
 }
 
+/// Accumulator that stores all of the added errors together with their positions.
+///
+/// Optionally sorts the errors by position, removes duplicates and limits how many errors
+/// are kept, see [`sorted`](PositionedErrorsAccumulator::sorted),
+/// [`deduplicated`](PositionedErrorsAccumulator::deduplicated) and
+/// [`with_limit`](PositionedErrorsAccumulator::with_limit).
+#[derive(Debug)]
+pub struct PositionedErrorsAccumulator<P, E> {
+    errors: Vec<(P, E)>,
+    sort: Option<fn(&P, &P) -> Ordering>,
+    dedup: Option<fn(&E, &E) -> bool>,
+    limit: Option<usize>,
+}
+
+impl<P, E> PositionedErrorsAccumulator<P, E> {
+    /// Creates a new accumulator, keeping all errors in the order they were added.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Only keeps `limit` errors, discarding the rest.
+    ///
+    /// When sorting, the errors at the smallest positions are kept, otherwise the first ones
+    /// that were added. When deduplicating, duplicates don't count towards the limit.
+    #[inline]
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+impl<P: Ord, E> PositionedErrorsAccumulator<P, E> {
+    /// Sorts the accumulated errors by their position. Errors at the same position keep the
+    /// order they were added in.
+    #[inline]
+    pub fn sorted(mut self) -> Self {
+        self.sort = Some(P::cmp);
+        self
+    }
+}
+
+impl<P, E: PartialEq> PositionedErrorsAccumulator<P, E> {
+    /// Discards errors equal to an already added error at the same position.
+    #[inline]
+    pub fn deduplicated(mut self) -> Self {
+        self.dedup = Some(E::eq);
+        self
+    }
+}
+
+impl<P, E> Default for PositionedErrorsAccumulator<P, E> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            errors: Vec::new(),
+            sort: None,
+            dedup: None,
+            limit: None,
+        }
+    }
+}
+
+impl<P, E> ErrorAccumulator<P, E> for PositionedErrorsAccumulator<P, E>
+where
+    P: Pos,
+{
+    type Accumulated = Vec<(P, E)>;
+
+    #[inline]
+    fn add_err(&mut self, err: E, pos: P) {
+        if let Some(eq) = self.dedup {
+            if self.errors.iter().any(|(p, e)| *p == pos && eq(e, &err)) {
+                return;
+            }
+        }
+
+        // insert after all errors at the same position, to keep the order they were added in
+        let index = match self.sort {
+            Some(cmp) => self
+                .errors
+                .binary_search_by(|(p, _)| match cmp(p, &pos) {
+                    Ordering::Greater => Ordering::Greater,
+                    _ => Ordering::Less,
+                })
+                .unwrap_or_else(|index| index),
+            None => self.errors.len(),
+        };
+
+        if matches!(self.limit, Some(limit) if index >= limit) {
+            return;
+        }
+
+        self.errors.insert(index, (pos, err));
+        if let Some(limit) = self.limit {
+            self.errors.truncate(limit);
+        }
+    }
+
+    #[inline]
+    fn finish(self) -> Self::Accumulated {
+        self.errors
+    }
+}
+
 /// Accumulator that saves all "best" errors.
 ///
 /// "Best" is defined as errors that happen at the furthest position into the input data.
@@ -181,3 +295,235 @@ where
         self.errors
     }
 }
+
+/// Something a parser expected to find in the input.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expected {
+    /// A literal token, like `,` or `true`.
+    Token(Cow<'static, str>),
+    /// A named grammar rule, like `number` or `string`.
+    Rule(Cow<'static, str>),
+    /// A literal tag of bytes.
+    Tag(Vec<u8>),
+}
+
+impl fmt::Display for Expected {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Token(token) => write!(f, "`{}`", token),
+            Expected::Rule(rule) => f.write_str(rule),
+            Expected::Tag(tag) => write!(f, "`{}`", String::from_utf8_lossy(tag).escape_debug()),
+        }
+    }
+}
+
+/// Errors that can describe what was expected instead of the input that caused them.
+pub trait Expectation {
+    /// Returns everything that would have been accepted at the error position.
+    fn expected(&self) -> Vec<Expected>;
+}
+
+/// Accumulator that merges the expectations of all "best" errors into an
+/// [`ExpectedSet`](ExpectedSet), allowing "expected `,`, `]` or number" style messages.
+///
+/// "Best" is defined as errors that happen at the furthest position into the input data.
+/// If a "better" error is added, all previous expectations are discarded.
+#[derive(Debug)]
+pub struct ExpectedSetAccumulator<P> {
+    set: ExpectedSet<P>,
+}
+
+impl<P> ExpectedSetAccumulator<P>
+where
+    P: Pos,
+{
+    /// Creates a new accumulator.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<P> Default for ExpectedSetAccumulator<P>
+where
+    P: Pos,
+{
+    #[inline]
+    fn default() -> Self {
+        Self {
+            set: ExpectedSet {
+                pos: P::zero(),
+                expected: Vec::new(),
+            },
+        }
+    }
+}
+
+impl<P, E> ErrorAccumulator<P, E> for ExpectedSetAccumulator<P>
+where
+    P: Pos,
+    E: Expectation,
+{
+    type Accumulated = ExpectedSet<P>;
+
+    #[inline]
+    fn add_err(&mut self, err: E, pos: P) {
+        match pos.offset().cmp(&self.set.pos.offset()) {
+            Ordering::Less => {
+                // do nothing, our existing expectations are better
+            }
+            Ordering::Greater => {
+                // the new error is better, replace existing expectations
+                self.set.pos = pos;
+                self.set.expected.clear();
+                self.set.extend(err.expected());
+            }
+            Ordering::Equal => {
+                // multiple errors at the same position
+                self.set.extend(err.expected());
+            }
+        }
+    }
+
+    #[inline]
+    fn finish(self) -> Self::Accumulated {
+        self.set
+    }
+}
+
+/// The deduplicated expectations of all errors at the furthest position,
+/// as accumulated by [`ExpectedSetAccumulator`](ExpectedSetAccumulator).
+///
+/// Displays as a message like "expected `,`, `]` or number at offset 42".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedSet<P> {
+    /// The position of the errors
+    pub pos: P,
+    /// Everything that would have been accepted at `pos`, in the order it was added
+    pub expected: Vec<Expected>,
+}
+
+impl<P> ExpectedSet<P> {
+    fn extend(&mut self, expected: Vec<Expected>) {
+        for e in expected {
+            if !self.expected.contains(&e) {
+                self.expected.push(e);
+            }
+        }
+    }
+}
+
+impl<P: Pos> fmt::Display for ExpectedSet<P> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_expected(&self.expected, self.pos.offset(), f)
+    }
+}
+
+/// Formats `expected` as "expected `,`, `]` or number at offset 42".
+pub(crate) fn fmt_expected(
+    expected: &[Expected],
+    offset: usize,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    match expected.split_last() {
+        None => f.write_str("unexpected input")?,
+        Some((last, [])) => write!(f, "expected {}", last)?,
+        Some((last, rest)) => {
+            f.write_str("expected ")?;
+            for (i, e) in rest.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}", e)?;
+            }
+            write!(f, " or {}", last)?;
+        }
+    }
+
+    write!(f, " at offset {}", offset)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestError(&'static [&'static str]);
+
+    impl Expectation for TestError {
+        fn expected(&self) -> Vec<Expected> {
+            self.0
+                .iter()
+                .map(|&e| match e {
+                    "number" => Expected::Rule(e.into()),
+                    _ => Expected::Token(e.into()),
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn expected_set_keeps_and_merges_furthest_expectations() {
+        let mut acc = ExpectedSetAccumulator::new();
+        acc.add_err(TestError(&["{"]), 40usize);
+        acc.add_err(TestError(&[","]), 42);
+        acc.add_err(TestError(&["]", ","]), 42);
+        acc.add_err(TestError(&["}"]), 41);
+        acc.add_err(TestError(&["number"]), 42);
+
+        let set = ErrorAccumulator::<_, TestError>::finish(acc);
+        assert_eq!(set.pos, 42);
+        assert_eq!(
+            set.expected,
+            &[
+                Expected::Token(",".into()),
+                Expected::Token("]".into()),
+                Expected::Rule("number".into())
+            ]
+        );
+        assert_eq!(set.to_string(), "expected `,`, `]` or number at offset 42");
+    }
+
+    #[test]
+    fn expected_set_formats_short_lists() {
+        let finish = |acc: ExpectedSetAccumulator<usize>| {
+            ErrorAccumulator::<_, TestError>::finish(acc).to_string()
+        };
+
+        let mut acc = ExpectedSetAccumulator::new();
+        acc.add_err(TestError(&[]), 3usize);
+        assert_eq!(finish(acc), "unexpected input at offset 3");
+
+        let mut acc = ExpectedSetAccumulator::new();
+        acc.add_err(TestError(&["true", "false"]), 3usize);
+        assert_eq!(finish(acc), "expected `true` or `false` at offset 3");
+    }
+
+    #[test]
+    fn positioned_errors_keep_their_positions() {
+        let mut acc = PositionedErrorsAccumulator::new();
+        acc.add_err("b", 3usize);
+        acc.add_err("a", 1);
+        acc.add_err("b", 3);
+
+        assert_eq!(acc.finish(), vec![(3, "b"), (1, "a"), (3, "b")]);
+    }
+
+    #[test]
+    fn positioned_errors_can_be_sorted_deduplicated_and_limited() {
+        let mut acc = PositionedErrorsAccumulator::new()
+            .sorted()
+            .deduplicated()
+            .with_limit(3);
+        acc.add_err("c", 5usize);
+        acc.add_err("a", 1);
+        acc.add_err("c", 5);
+        acc.add_err("b", 1);
+        acc.add_err("d", 0);
+
+        // `d` is kept instead of `c`, as it is at a smaller position
+        assert_eq!(acc.finish(), vec![(0, "d"), (1, "a"), (1, "b")]);
+    }
+}
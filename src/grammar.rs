@@ -0,0 +1,834 @@
+//! Grammars built from inspectable expressions, instead of opaque closures.
+//!
+//! A [`Grammar`](Grammar) consists of named rules, each defined by an [`Expr`](Expr). The same
+//! grammar both parses byte slices (see [`Grammar::parser`](Grammar::parser)) and documents
+//! itself as EBNF text (see [`Grammar::to_ebnf`](Grammar::to_ebnf)) or SVG railroad diagrams
+//! (see [`Grammar::to_svg`](Grammar::to_svg)), so the documentation can't drift apart from the
+//! implementation.
+//!
+//! Only available with the `grammar` feature enabled.
+//!
+//! ```
+//! use pahs::grammar::{Expr, Grammar};
+//! use pahs::slice::BytePos;
+//! use pahs::ParseDriver;
+//!
+//! let grammar = Grammar::new()
+//!     .rule(
+//!         "list",
+//!         Expr::seq(vec![
+//!             Expr::tag("["),
+//!             Expr::zero_or_more(Expr::rule("item")),
+//!             Expr::tag("]"),
+//!         ]),
+//!     )
+//!     .rule("item", Expr::alt(vec![Expr::tag("a"), Expr::rule("list")]));
+//!
+//! assert_eq!(
+//!     grammar.to_ebnf(),
+//!     "list = \"[\" , { item } , \"]\" ;\nitem = \"a\" | list ;\n"
+//! );
+//!
+//! let (pos, parsed) = grammar.parser("list")(&mut ParseDriver::new(), BytePos::new(b"[a[]]!"))
+//!     .unwrap();
+//! assert_eq!(parsed, b"[a[]]");
+//! assert_eq!(pos.offset, 5);
+//! ```
+
+use std::fmt::{self, Write};
+use std::sync::Arc;
+
+use snafu::Snafu;
+
+use crate::combinators::{nested, one_or_more, optional, rule, zero_or_more};
+use crate::error_accumulator::{Expectation, Expected};
+use crate::slice::{match_tag, BytePos, TagError};
+use crate::{DriverError, ErrorKind, ParseDriver, Progress, Recoverable};
+
+/// A grammar expression, describing the structure of the input it matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    /// Matches a literal tag of bytes
+    Tag(Arc<[u8]>),
+    /// Matches the named rule of the grammar
+    Rule(&'static str),
+    /// Matches all expressions, one after the other
+    Sequence(Vec<Expr>),
+    /// Matches the first matching expression, see
+    /// [`Alternate`](crate::combinators::Alternate)
+    Alternation(Vec<Expr>),
+    /// Matches the expression, or nothing, see [`optional`](crate::combinators::optional)
+    Optional(Box<Expr>),
+    /// Matches the expression any number of times, see
+    /// [`zero_or_more`](crate::combinators::zero_or_more)
+    ZeroOrMore(Box<Expr>),
+    /// Matches the expression at least once, see
+    /// [`one_or_more`](crate::combinators::one_or_more)
+    OneOrMore(Box<Expr>),
+}
+
+impl Expr {
+    /// Creates an expression matching the literal `tag`.
+    #[inline]
+    pub fn tag(tag: impl AsRef<[u8]>) -> Self {
+        Expr::Tag(tag.as_ref().into())
+    }
+
+    /// Creates an expression matching the rule `name`.
+    #[inline]
+    pub fn rule(name: &'static str) -> Self {
+        Expr::Rule(name)
+    }
+
+    /// Creates an expression matching all `exprs`, one after the other.
+    #[inline]
+    pub fn seq(exprs: Vec<Expr>) -> Self {
+        Expr::Sequence(exprs)
+    }
+
+    /// Creates an expression matching the first matching of `exprs`.
+    #[inline]
+    pub fn alt(exprs: Vec<Expr>) -> Self {
+        Expr::Alternation(exprs)
+    }
+
+    /// Creates an expression matching `expr`, or nothing.
+    #[inline]
+    pub fn optional(expr: Expr) -> Self {
+        Expr::Optional(Box::new(expr))
+    }
+
+    /// Creates an expression matching `expr` any number of times.
+    #[inline]
+    pub fn zero_or_more(expr: Expr) -> Self {
+        Expr::ZeroOrMore(Box::new(expr))
+    }
+
+    /// Creates an expression matching `expr` at least once.
+    #[inline]
+    pub fn one_or_more(expr: Expr) -> Self {
+        Expr::OneOrMore(Box::new(expr))
+    }
+}
+
+/// Named rules, defined by [`Expr`](Expr)essions. See the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Grammar {
+    rules: Vec<(&'static str, Expr)>,
+}
+
+/// Errors that may happen when parsing via a [`Grammar`](Grammar).
+#[derive(Debug, Clone, PartialEq, Eq, Snafu)]
+#[snafu(visibility = "pub(crate)")]
+#[non_exhaustive]
+pub enum GrammarError {
+    /// A tag didn't match.
+    #[snafu(display("{}", source))]
+    Tag {
+        /// The error of the tag
+        source: TagError,
+    },
+    /// An alternation without any expressions was reached.
+    #[snafu(display("empty alternation at offset {}", offset))]
+    EmptyAlternation {
+        /// The offset of the alternation
+        offset: usize,
+    },
+    /// A rule that isn't part of the grammar was referenced.
+    #[snafu(display("unknown rule `{}`", name))]
+    UnknownRule {
+        /// The name of the rule
+        name: &'static str,
+    },
+    /// The combinators failed, e.g. as the nesting depth limit was exceeded.
+    #[snafu(display("{}", source))]
+    Driver {
+        /// The error of the combinators
+        source: DriverError,
+    },
+}
+
+impl Recoverable for GrammarError {
+    #[inline]
+    fn kind(&self) -> ErrorKind {
+        match self {
+            GrammarError::Tag { .. } | GrammarError::EmptyAlternation { .. } => {
+                ErrorKind::Backtrack
+            }
+            GrammarError::UnknownRule { .. } | GrammarError::Driver { .. } => ErrorKind::Fatal,
+        }
+    }
+
+    #[inline]
+    fn as_expectation(&self) -> Option<&dyn Expectation> {
+        Some(self)
+    }
+
+    #[inline]
+    fn from_driver_error(source: DriverError) -> Self {
+        GrammarError::Driver { source }
+    }
+}
+
+impl Expectation for GrammarError {
+    #[inline]
+    fn expected(&self) -> Vec<Expected> {
+        match self {
+            GrammarError::Tag { source } => source.expected(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Grammar {
+    /// Creates a new grammar without rules.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the rule `name`, defined by `expr`, replacing a previous definition.
+    #[inline]
+    pub fn rule(mut self, name: &'static str, expr: Expr) -> Self {
+        match self.rules.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, e)) => *e = expr,
+            None => self.rules.push((name, expr)),
+        }
+        self
+    }
+
+    /// Returns the definition of the rule `name`.
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&Expr> {
+        self.rules
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, expr)| expr)
+    }
+
+    /// Returns all rules, in the order they were added.
+    #[inline]
+    pub fn rules(&self) -> impl Iterator<Item = (&'static str, &Expr)> {
+        self.rules.iter().map(|(name, expr)| (*name, expr))
+    }
+
+    /// Returns a parser for the rule `name`, returning the matched input.
+    ///
+    /// Every rule runs as a named [`rule`](crate::combinators::rule), so it shows up in traces
+    /// and profiles, and one level deeper via [`nested`](crate::combinators::nested), so
+    /// [`ParseDriver::set_max_depth`](ParseDriver::set_max_depth) protects against deeply
+    /// nested input.
+    #[inline]
+    pub fn parser<'g, 'a, S>(
+        &'g self,
+        name: &'static str,
+    ) -> impl Fn(&mut ParseDriver<S>, BytePos<'a>) -> Progress<BytePos<'a>, &'a [u8], GrammarError> + 'g
+    {
+        move |pd, start| {
+            let (pos, ()) = pahs!(self.parse_rule(pd, start, name));
+            pos.success(&start.s[..pos.offset - start.offset])
+        }
+    }
+
+    fn parse_rule<'a, S>(
+        &self,
+        pd: &mut ParseDriver<S>,
+        pos: BytePos<'a>,
+        name: &'static str,
+    ) -> Progress<BytePos<'a>, (), GrammarError> {
+        let expr = match self.get(name) {
+            Some(expr) => expr,
+            None => return pos.failure(GrammarError::UnknownRule { name }),
+        };
+
+        nested(rule(name, |pd, pos| self.parse_expr(pd, pos, expr)))(pd, pos)
+    }
+
+    fn parse_expr<'a, S>(
+        &self,
+        pd: &mut ParseDriver<S>,
+        pos: BytePos<'a>,
+        expr: &Expr,
+    ) -> Progress<BytePos<'a>, (), GrammarError> {
+        match expr {
+            Expr::Tag(t) => match_tag(pos, t, |slice| slice == &t[..])
+                .map(|_| ())
+                .map_err(|source| GrammarError::Tag { source }),
+            Expr::Rule(name) => self.parse_rule(pd, pos, name),
+            Expr::Sequence(exprs) => {
+                let mut pos = pos;
+                for expr in exprs {
+                    let (new_pos, ()) = pahs!(self.parse_expr(pd, pos, expr));
+                    pos = new_pos;
+                }
+                pos.success(())
+            }
+            Expr::Alternation(exprs) => exprs
+                .iter()
+                .fold(pd.alternate(pos), |alternate, expr| {
+                    alternate.one(|pd, pos| self.parse_expr(pd, pos, expr))
+                })
+                .try_finish()
+                .unwrap_or_else(|| {
+                    pos.failure(GrammarError::EmptyAlternation { offset: pos.offset })
+                }),
+            Expr::Optional(expr) => {
+                optional(|pd, pos| self.parse_expr(pd, pos, expr))(pd, pos).map(|_| ())
+            }
+            Expr::ZeroOrMore(expr) => {
+                zero_or_more(|pd, pos| self.parse_expr(pd, pos, expr))(pd, pos).map(|_| ())
+            }
+            Expr::OneOrMore(expr) => {
+                one_or_more(|pd, pos| self.parse_expr(pd, pos, expr))(pd, pos).map(|_| ())
+            }
+        }
+    }
+
+    /// Renders the grammar as EBNF (ISO/IEC 14977), one rule per line.
+    ///
+    /// Tags of printable ASCII characters are rendered as quoted strings, all others as special
+    /// sequences listing their bytes, like `? 0xC0 0x80 ?`. Empty alternations, which never
+    /// match, are rendered as the special sequence `? no alternative ?`.
+    #[inline]
+    pub fn to_ebnf(&self) -> String {
+        let mut out = String::new();
+        // writing to a `String` can't fail
+        self.write_ebnf(&mut out).unwrap();
+        out
+    }
+
+    fn write_ebnf(&self, out: &mut String) -> fmt::Result {
+        for (name, expr) in &self.rules {
+            write!(out, "{} = ", name)?;
+            write_ebnf_expr(out, expr, Precedence::Alternation)?;
+            out.push_str(" ;\n");
+        }
+        Ok(())
+    }
+
+    /// Renders the grammar as an SVG image of railroad diagrams, one per rule.
+    #[inline]
+    pub fn to_svg(&self) -> String {
+        let mut out = String::new();
+        // writing to a `String` can't fail
+        self.write_svg(&mut out).unwrap();
+        out
+    }
+
+    fn write_svg(&self, out: &mut String) -> fmt::Result {
+        const MARGIN: i32 = 20;
+        const TITLE: i32 = 24;
+        const MARKER: i32 = 10;
+
+        let diagrams = self
+            .rules
+            .iter()
+            .map(|(name, expr)| (*name, Diagram::of(expr)))
+            .collect::<Vec<_>>();
+
+        let width = diagrams
+            .iter()
+            .map(|(_, diagram)| diagram.width() + 2 * MARKER)
+            .max()
+            .unwrap_or(0)
+            + 2 * MARGIN;
+        let height = diagrams
+            .iter()
+            .map(|(_, diagram)| TITLE + diagram.up() + diagram.down() + MARGIN)
+            .sum::<i32>()
+            + MARGIN;
+
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = width,
+            h = height
+        )?;
+        out.push_str(
+            "<style>\
+             path { fill: none; stroke: #000; stroke-width: 1.5; } \
+             rect { fill: #ffd; stroke: #000; stroke-width: 1.5; } \
+             text { font: 14px monospace; } \
+             text.rule { font-weight: bold; }\
+             </style>\n",
+        );
+
+        let mut top = MARGIN;
+        for (name, diagram) in &diagrams {
+            writeln!(
+                out,
+                r#"<text class="rule" x="{}" y="{}">{}</text>"#,
+                MARGIN,
+                top + 14,
+                Xml(name)
+            )?;
+
+            let y = top + TITLE + diagram.up();
+            let x = MARGIN + MARKER;
+            // start and end markers
+            writeln!(
+                out,
+                r#"<path d="M{} {} v20 M{} {} h{}"/>"#,
+                MARGIN,
+                y - 10,
+                MARGIN,
+                y,
+                MARKER
+            )?;
+            diagram.write(out, x, y)?;
+            writeln!(
+                out,
+                r#"<path d="M{} {} h{} M{} {} v20"/>"#,
+                x + diagram.width(),
+                y,
+                MARKER,
+                x + diagram.width() + MARKER,
+                y - 10
+            )?;
+
+            top += TITLE + diagram.up() + diagram.down() + MARGIN;
+        }
+
+        out.push_str("</svg>\n");
+        Ok(())
+    }
+}
+
+/// The special sequence rendered for empty alternations, which never match
+const NO_ALTERNATIVE: &str = "? no alternative ?";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Alternation,
+    Sequence,
+}
+
+fn write_ebnf_expr(out: &mut String, expr: &Expr, parent: Precedence) -> fmt::Result {
+    let (precedence, separator, exprs) = match expr {
+        Expr::Tag(tag) => return write_ebnf_tag(out, tag),
+        Expr::Rule(name) => return out.write_str(name),
+        Expr::Optional(expr) => {
+            out.push_str("[ ");
+            write_ebnf_expr(out, expr, Precedence::Alternation)?;
+            return out.write_str(" ]");
+        }
+        Expr::ZeroOrMore(expr) => {
+            out.push_str("{ ");
+            write_ebnf_expr(out, expr, Precedence::Alternation)?;
+            return out.write_str(" }");
+        }
+        Expr::OneOrMore(expr) => {
+            // a sequence itself, but sequences never need parentheses
+            write_ebnf_expr(out, expr, Precedence::Sequence)?;
+            out.push_str(" , { ");
+            write_ebnf_expr(out, expr, Precedence::Alternation)?;
+            return out.write_str(" }");
+        }
+        Expr::Sequence(exprs) => (Precedence::Sequence, " , ", exprs),
+        Expr::Alternation(exprs) => (Precedence::Alternation, " | ", exprs),
+    };
+
+    match exprs.len() {
+        // ISO EBNF has neither, an empty string matches the same as an empty sequence, while
+        // an empty alternation never matches
+        0 if precedence == Precedence::Alternation => out.write_str(NO_ALTERNATIVE),
+        0 => out.write_str("\"\""),
+        1 => write_ebnf_expr(out, &exprs[0], parent),
+        _ => {
+            let parenthesize = parent > precedence;
+            if parenthesize {
+                out.push_str("( ");
+            }
+            for (i, expr) in exprs.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(separator);
+                }
+                write_ebnf_expr(out, expr, precedence)?;
+            }
+            if parenthesize {
+                out.push_str(" )");
+            }
+            Ok(())
+        }
+    }
+}
+
+fn write_ebnf_tag(out: &mut String, tag: &[u8]) -> fmt::Result {
+    let printable = tag.iter().all(|b| (0x20..0x7f).contains(b));
+    match std::str::from_utf8(tag) {
+        Ok(s) if printable && !s.contains('"') => write!(out, "\"{}\"", s),
+        Ok(s) if printable && !s.contains('\'') => write!(out, "'{}'", s),
+        _ => {
+            out.push('?');
+            for b in tag {
+                write!(out, " 0x{:02X}", b)?;
+            }
+            out.write_str(" ?")
+        }
+    }
+}
+
+/// The layout of a railroad diagram, with the line entering and leaving it on its baseline.
+#[derive(Debug)]
+enum Diagram {
+    Terminal(String),
+    NonTerminal(&'static str),
+    Skip,
+    Sequence(Vec<Diagram>),
+    Choice(Vec<Diagram>),
+    Loop(Box<Diagram>),
+}
+
+/// The horizontal space between the elements of a sequence
+const GAP: i32 = 10;
+/// The vertical space between the branches of a choice
+const VGAP: i32 = 8;
+/// The radius of the arcs leading into branches and loops
+const ARC: i32 = 10;
+/// The height of a box
+const BOX: i32 = 24;
+/// The width of a character in a box
+const CHAR: i32 = 9;
+
+impl Diagram {
+    fn of(expr: &Expr) -> Self {
+        match expr {
+            Expr::Tag(tag) => {
+                let mut text = String::new();
+                // writing to a `String` can't fail
+                write_ebnf_tag(&mut text, tag).unwrap();
+                Diagram::Terminal(text)
+            }
+            Expr::Rule(name) => Diagram::NonTerminal(name),
+            Expr::Sequence(exprs) => Diagram::Sequence(exprs.iter().map(Diagram::of).collect()),
+            Expr::Alternation(exprs) if exprs.is_empty() => {
+                Diagram::Terminal(NO_ALTERNATIVE.into())
+            }
+            Expr::Alternation(exprs) => Diagram::Choice(exprs.iter().map(Diagram::of).collect()),
+            Expr::Optional(expr) => Diagram::Choice(vec![Diagram::Skip, Diagram::of(expr)]),
+            Expr::ZeroOrMore(expr) => Diagram::Choice(vec![
+                Diagram::Skip,
+                Diagram::Loop(Box::new(Diagram::of(expr))),
+            ]),
+            Expr::OneOrMore(expr) => Diagram::Loop(Box::new(Diagram::of(expr))),
+        }
+    }
+
+    fn text_width(text: &str) -> i32 {
+        text.chars().count() as i32 * CHAR + 2 * GAP
+    }
+
+    fn width(&self) -> i32 {
+        match self {
+            Diagram::Terminal(text) => Self::text_width(text),
+            Diagram::NonTerminal(name) => Self::text_width(name),
+            Diagram::Skip => 0,
+            Diagram::Sequence(items) => {
+                items.iter().map(Diagram::width).sum::<i32>()
+                    + GAP * (items.len() as i32 - 1).max(0)
+            }
+            Diagram::Choice(branches) => {
+                branches.iter().map(Diagram::width).max().unwrap_or(0) + 4 * ARC
+            }
+            Diagram::Loop(item) => item.width() + 4 * ARC,
+        }
+    }
+
+    /// The height above the baseline
+    fn up(&self) -> i32 {
+        match self {
+            Diagram::Terminal(..) | Diagram::NonTerminal(..) => BOX / 2,
+            Diagram::Skip => 0,
+            Diagram::Sequence(items) => items.iter().map(Diagram::up).max().unwrap_or(0),
+            Diagram::Choice(branches) => branches.first().map_or(0, Diagram::up),
+            Diagram::Loop(item) => item.up(),
+        }
+    }
+
+    /// The height below the baseline
+    fn down(&self) -> i32 {
+        match self {
+            Diagram::Terminal(..) | Diagram::NonTerminal(..) => BOX / 2,
+            Diagram::Skip => 0,
+            Diagram::Sequence(items) => items.iter().map(Diagram::down).max().unwrap_or(0),
+            Diagram::Choice(branches) => Self::branch_offsets(branches)
+                .zip(branches)
+                .map(|(offset, branch)| offset + branch.down())
+                .max()
+                .unwrap_or(0),
+            Diagram::Loop(item) => Self::loop_offset(item),
+        }
+    }
+
+    /// The offsets of the baselines of all branches from the baseline of the choice
+    fn branch_offsets(branches: &[Diagram]) -> impl Iterator<Item = i32> + '_ {
+        let mut offset = 0;
+        branches.iter().enumerate().map(move |(i, branch)| {
+            if i > 0 {
+                let prev = &branches[i - 1];
+                offset = (offset + prev.down() + VGAP + branch.up()).max(offset + 2 * ARC);
+            }
+            offset
+        })
+    }
+
+    /// The offset of the line leading back from the end of a loop
+    fn loop_offset(item: &Diagram) -> i32 {
+        (item.down() + VGAP).max(2 * ARC)
+    }
+
+    /// Writes the diagram, with its baseline entering at `x`, `y`.
+    fn write(&self, out: &mut String, x: i32, y: i32) -> fmt::Result {
+        match self {
+            Diagram::Terminal(text) => Self::write_box(out, x, y, text, BOX / 2),
+            Diagram::NonTerminal(name) => Self::write_box(out, x, y, name, 0),
+            Diagram::Skip => Ok(()),
+            Diagram::Sequence(items) => {
+                let mut x = x;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(out, r#"<path d="M{} {} h{}"/>"#, x, y, GAP)?;
+                        x += GAP;
+                    }
+                    item.write(out, x, y)?;
+                    x += item.width();
+                }
+                Ok(())
+            }
+            Diagram::Choice(branches) => {
+                let width = self.width();
+                let end = x + width;
+                for (offset, branch) in Self::branch_offsets(branches).zip(branches) {
+                    let branch_end = x + 2 * ARC + branch.width();
+                    if offset == 0 {
+                        writeln!(
+                            out,
+                            r#"<path d="M{} {} h{} M{} {} H{}"/>"#,
+                            x,
+                            y,
+                            2 * ARC,
+                            branch_end,
+                            y,
+                            end
+                        )?;
+                    } else {
+                        let branch_y = y + offset;
+                        writeln!(
+                            out,
+                            r#"<path d="M{x} {y} a{r} {r} 0 0 1 {r} {r} V{by1} a{r} {r} 0 0 0 {r} {r} M{bx} {by} H{ex1} a{r} {r} 0 0 0 {r} -{r} V{y1} a{r} {r} 0 0 1 {r} -{r}"/>"#,
+                            x = x,
+                            y = y,
+                            r = ARC,
+                            by1 = branch_y - ARC,
+                            bx = branch_end,
+                            by = branch_y,
+                            ex1 = end - 2 * ARC,
+                            y1 = y + ARC
+                        )?;
+                    }
+                    branch.write(out, x + 2 * ARC, y + offset)?;
+                }
+                Ok(())
+            }
+            Diagram::Loop(item) => {
+                let item_end = x + 2 * ARC + item.width();
+                let back_y = y + Self::loop_offset(item);
+                writeln!(
+                    out,
+                    r#"<path d="M{x} {y} h{d} M{ie} {y} h{d} M{ie} {y} a{r} {r} 0 0 1 {r} {r} V{by1} a{r} {r} 0 0 1 -{r} {r} H{x2} a{r} {r} 0 0 1 -{r} -{r} V{y1} a{r} {r} 0 0 1 {r} -{r}"/>"#,
+                    x = x,
+                    y = y,
+                    d = 2 * ARC,
+                    ie = item_end,
+                    r = ARC,
+                    by1 = back_y - ARC,
+                    x2 = x + 2 * ARC,
+                    y1 = y + ARC
+                )?;
+                item.write(out, x + 2 * ARC, y)
+            }
+        }
+    }
+
+    fn write_box(out: &mut String, x: i32, y: i32, text: &str, radius: i32) -> fmt::Result {
+        let width = Self::text_width(text);
+        writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}"/>"#,
+            x,
+            y - BOX / 2,
+            width,
+            BOX,
+            radius
+        )?;
+        writeln!(
+            out,
+            r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+            x + width / 2,
+            y + 5,
+            Xml(text)
+        )
+    }
+}
+
+/// Displays text with XML special characters escaped.
+struct Xml<'t>(&'t str);
+
+impl fmt::Display for Xml<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&apos;")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// `value = "null" | "[" [ value { "," value } ] "]"`
+    fn json_ish() -> Grammar {
+        Grammar::new()
+            .rule(
+                "value",
+                Expr::alt(vec![Expr::tag("null"), Expr::rule("array")]),
+            )
+            .rule(
+                "array",
+                Expr::seq(vec![
+                    Expr::tag("["),
+                    Expr::optional(Expr::seq(vec![
+                        Expr::rule("value"),
+                        Expr::zero_or_more(Expr::seq(vec![Expr::tag(","), Expr::rule("value")])),
+                    ])),
+                    Expr::tag("]"),
+                ]),
+            )
+    }
+
+    #[test]
+    fn it_parses_via_the_grammar() {
+        let grammar = json_ish();
+        let pd = &mut ParseDriver::new();
+
+        let (pos, parsed) = grammar.parser("value")(pd, BytePos::new(b"[null,[],[null]]")).unwrap();
+        assert_eq!(pos.offset, 16);
+        assert_eq!(parsed, b"[null,[],[null]]");
+
+        let (pos, err) = grammar.parser("value")(pd, BytePos::new(b"[null,]")).unwrap_err();
+        assert_eq!(pos.offset, 5);
+        assert_eq!(
+            err,
+            GrammarError::Tag {
+                source: TagError::TagMismatch {
+                    offset: 5,
+                    expected: b"]"[..].into(),
+                    found: b",".to_vec(),
+                }
+            }
+        );
+        assert!(err.recoverable());
+    }
+
+    #[test]
+    fn it_fails_on_unknown_rules() {
+        let grammar = Grammar::new().rule("a", Expr::rule("b"));
+        let pd = &mut ParseDriver::new();
+
+        let (_, err) = grammar.parser("a")(pd, BytePos::new(b"")).unwrap_err();
+        assert_eq!(err, GrammarError::UnknownRule { name: "b" });
+        assert_eq!(err.kind(), ErrorKind::Fatal);
+    }
+
+    #[test]
+    fn it_limits_the_depth_of_rules() {
+        let grammar = json_ish();
+        let pd = &mut ParseDriver::new();
+        pd.set_max_depth(4);
+
+        assert!(grammar.parser("value")(pd, BytePos::new(b"[null]")).is_ok());
+        let (_, err) = grammar.parser("value")(pd, BytePos::new(b"[[null]]")).unwrap_err();
+        assert_eq!(
+            err,
+            GrammarError::Driver {
+                source: DriverError::DepthLimitExceeded {
+                    offset: 2,
+                    max_depth: 4
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn it_renders_ebnf() {
+        assert_eq!(
+            json_ish().to_ebnf(),
+            "value = \"null\" | array ;\n\
+             array = \"[\" , [ value , { \",\" , value } ] , \"]\" ;\n"
+        );
+
+        let grammar = Grammar::new()
+            .rule(
+                "a",
+                Expr::seq(vec![
+                    Expr::alt(vec![Expr::tag("\""), Expr::tag(b"\xC0\x80")]),
+                    Expr::one_or_more(Expr::alt(vec![Expr::rule("b"), Expr::rule("c")])),
+                ]),
+            )
+            .rule("b", Expr::seq(vec![]))
+            .rule("c", Expr::tag("'\""));
+        assert_eq!(
+            grammar.to_ebnf(),
+            "a = ( '\"' | ? 0xC0 0x80 ? ) , ( b | c ) , { b | c } ;\n\
+             b = \"\" ;\n\
+             c = ? 0x27 0x22 ? ;\n"
+        );
+    }
+
+    #[test]
+    fn ebnf_and_parser_agree_on_empty_expressions() {
+        let grammar = Grammar::new()
+            .rule("never", Expr::alt(vec![]))
+            .rule("empty", Expr::seq(vec![]))
+            .rule("maybe", Expr::optional(Expr::alt(vec![])));
+        assert_eq!(
+            grammar.to_ebnf(),
+            "never = ? no alternative ? ;\n\
+             empty = \"\" ;\n\
+             maybe = [ ? no alternative ? ] ;\n"
+        );
+        assert!(grammar.to_svg().contains(">? no alternative ?</text>"));
+
+        let pd = &mut ParseDriver::new();
+        for input in &[&b""[..], b"a"] {
+            let (_, err) = grammar.parser("never")(pd, BytePos::new(input)).unwrap_err();
+            assert_eq!(err, GrammarError::EmptyAlternation { offset: 0 });
+
+            let (pos, parsed) = grammar.parser("empty")(pd, BytePos::new(input)).unwrap();
+            assert_eq!((pos.offset, parsed), (0, &b""[..]));
+
+            let (pos, parsed) = grammar.parser("maybe")(pd, BytePos::new(input)).unwrap();
+            assert_eq!((pos.offset, parsed), (0, &b""[..]));
+        }
+    }
+
+    #[test]
+    fn it_renders_svg() {
+        let svg = json_ish().to_svg();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains(r#"<text class="rule" x="20" y="34">value</text>"#));
+        assert!(svg.contains(">&quot;null&quot;</text>"));
+        assert!(svg.contains(">array</text>"));
+        // `"null"`, `array`, `"["`, `value`, `","`, `value` and `"]"`
+        assert_eq!(svg.matches("<rect").count(), 7);
+    }
+}
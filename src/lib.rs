@@ -27,42 +27,109 @@ macro_rules! pahs {
     };
 }
 
-macro_rules! opt_assert {
-    ($cond:expr $(,)?) => {
-        if cfg!(any(test, feature = "loop_assert")) {
-            assert!($cond);
-        } else {
-            debug_assert!($cond);
-        }
-    };
-
-    ($cond:expr, $($arg:tt)+) => {{}
-        if cfg!(any(test, feature = "loop_assert")) {
-            assert!($cond, $($arg)+)
-        } else {
-            debug_assert!($cond, $($arg)+);
-        }
-    };
-}
+// allows the derive macros to refer to `::pahs` in this crate's own tests
+#[cfg(all(test, feature = "derive"))]
+extern crate self as pahs;
 
 pub mod combinators;
+pub mod diagnostic;
+mod driver_error;
 pub mod error_accumulator;
+#[cfg(feature = "grammar")]
+pub mod grammar;
 mod parse_driver;
 mod pos;
+pub mod profile;
 mod progress;
 mod push;
 pub mod slice;
+pub mod testing;
+pub mod trace;
 
-pub use self::parse_driver::ParseDriver;
+pub use self::driver_error::DriverError;
+pub use self::parse_driver::{
+    Budget, CancellationToken, FurthestFailure, NoProgressPolicy, ParseDriver,
+};
 pub use self::pos::Pos;
-pub use self::progress::Progress;
+pub use self::progress::{Located, Progress};
 pub use self::push::Push;
 
 /// Indicates if an error allows a parent parser to recover and try something else.
 ///
 /// Errors usually are irrecoverable if the input is well-formed,
 /// but other constraints failed.
+///
+/// # Migrating from earlier versions
+///
+/// Previously, only [`recoverable`](Recoverable::recoverable) had to be implemented. Implement
+/// [`kind`](Recoverable::kind) instead, returning [`ErrorKind::Backtrack`](ErrorKind::Backtrack)
+/// where `recoverable` returned `true`, and [`ErrorKind::Cut`](ErrorKind::Cut) where it
+/// returned `false`. `recoverable` is derived from it.
+///
+/// [`from_driver_error`](Recoverable::from_driver_error) is required as well, as the
+/// combinators never panic on errors the error type can't represent. Add a variant wrapping
+/// the [`DriverError`](DriverError) to the error type, and classify it as
+/// [`ErrorKind::Fatal`](ErrorKind::Fatal).
 pub trait Recoverable {
     /// Returns `true` if the parse failure is recoverable, `false` otherwise.
-    fn recoverable(&self) -> bool;
+    ///
+    /// Returns `true` if [`kind`](Recoverable::kind) is
+    /// [`ErrorKind::Backtrack`](ErrorKind::Backtrack).
+    #[inline]
+    fn recoverable(&self) -> bool {
+        self.kind() == ErrorKind::Backtrack
+    }
+
+    /// Classifies the error, see [`ErrorKind`](ErrorKind).
+    fn kind(&self) -> ErrorKind;
+
+    /// Describes what was expected instead of the input that caused this error, for the
+    /// furthest failure tracked via
+    /// [`ParseDriver::track_furthest_failure`](ParseDriver::track_furthest_failure).
+    ///
+    /// Returns `None` by default, in which case only the offset of the failure is tracked.
+    /// Errors implementing [`Expectation`](error_accumulator::Expectation) usually
+    /// return `Some(self)`.
+    #[inline]
+    fn as_expectation(&self) -> Option<&dyn error_accumulator::Expectation> {
+        None
+    }
+
+    /// Converts an error raised by the combinators themselves into this error type,
+    /// like a repeated parser that did not progress or an exhausted budget.
+    ///
+    /// The combinators fail with the converted error, so error types have to be able to
+    /// represent every [`DriverError`](DriverError), usually by wrapping it in a dedicated
+    /// variant classified as [`ErrorKind::Fatal`](ErrorKind::Fatal).
+    fn from_driver_error(err: DriverError) -> Self
+    where
+        Self: Sized;
+}
+
+/// Classifies errors by how parent parsers should react to them.
+///
+/// Only [`Backtrack`](ErrorKind::Backtrack) errors are recoverable. The combinators react
+/// to them as follows, while all other kinds are passed on as failures:
+///
+/// - [`Alternate`](combinators::Alternate) tries the next branch.
+/// - [`optional`](combinators::optional) succeeds with `None`.
+/// - [`zero_or_more`](combinators::zero_or_more) and [`one_or_more`](combinators::one_or_more)
+///   stop, succeeding with the values parsed so far.
+/// - [`count`](combinators::count) fails, as all elements are required.
+///
+/// The other kinds differ in what the caller of the whole parse can do about them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The input didn't match, but something else might. Try the next alternative.
+    Backtrack,
+    /// The input was recognized, but is malformed: the current rule is aborted,
+    /// without trying any alternatives. The caller may still resynchronize and continue
+    /// after the malformed part.
+    Cut,
+    /// The input ended before the parser could decide if it matches. The parse might
+    /// succeed once more data is available, so it is not treated as a mismatch.
+    Incomplete,
+    /// An internal error that was not caused by the input, like a bug in the grammar.
+    /// Never recovered from.
+    Fatal,
 }
@@ -1,6 +1,18 @@
-use crate::combinators::{optional, Alternate};
-use crate::error_accumulator::{ErrorAccumulator, LastErrorOnly};
-use crate::{Pos, Progress, Recoverable};
+use std::borrow::Cow;
+use std::fmt::{self, Debug};
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::combinators::{
+    context, nested, optional, recover_with, rule, Alternate, Ambiguity, ContextFrame, Contextual,
+    Recovered,
+};
+use crate::error_accumulator::{fmt_expected, ErrorAccumulator, Expected, LastErrorOnly};
+use crate::profile::Profile;
+use crate::trace::Trace;
+use crate::{DriverError, Pos, Progress, Recoverable};
 
 /// Maintains (optional) parsing state/context and serves as an easy entry point
 /// for some of the combinators.
@@ -8,6 +20,129 @@ use crate::{Pos, Progress, Recoverable};
 pub struct ParseDriver<S = ()> {
     /// The parser state
     pub state: S,
+    pub(crate) context: Vec<ContextFrame>,
+    furthest_failure: Option<FurthestFailure>,
+    trace: Option<Trace>,
+    profile: Option<Profile>,
+    budget: Option<Budget>,
+    spent: usize,
+    /// The furthest offset reached by the budgeted invocation currently running
+    reached: usize,
+    /// The elements charged to the invocations nested in the budgeted invocation currently running
+    charged: usize,
+    pub(crate) depth: usize,
+    max_depth: Option<usize>,
+    cancellation: Option<CancellationToken>,
+    no_progress_policy: NoProgressPolicy,
+    ambiguities: Option<Vec<Ambiguity>>,
+    /// Clones the parser state while detecting ambiguities
+    clone_state: Option<fn(&S) -> S>,
+}
+
+/// The side effects of parsers on a [`ParseDriver`](ParseDriver), see
+/// [`ParseDriver::save_side_effects`](ParseDriver::save_side_effects).
+#[derive(Debug)]
+pub(crate) struct SideEffects<S> {
+    state: Option<S>,
+    furthest_failure: Option<FurthestFailure>,
+    ambiguities: Option<usize>,
+    spent: usize,
+    reached: usize,
+    charged: usize,
+    trace: Option<usize>,
+    profile: Option<Profile>,
+}
+
+/// How the repetition combinators react to a parser that succeeds without progressing, see
+/// [`ParseDriver::set_no_progress_policy`](ParseDriver::set_no_progress_policy).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NoProgressPolicy {
+    /// Fail with [`DriverError::NoProgress`](crate::DriverError::NoProgress), converted via
+    /// [`Recoverable::from_driver_error`](crate::Recoverable::from_driver_error). Never panics.
+    Error,
+    /// Panic, unless the `panic_free` feature is enabled, in which case this behaves like
+    /// [`Error`](NoProgressPolicy::Error).
+    Panic,
+    /// Ignore it. [`zero_or_more`](crate::combinators::zero_or_more) and
+    /// [`one_or_more`](crate::combinators::one_or_more) stop successfully, as they would repeat
+    /// the parser forever, while [`count`](crate::combinators::count) continues.
+    Ignore,
+}
+
+impl Default for NoProgressPolicy {
+    #[inline]
+    fn default() -> Self {
+        NoProgressPolicy::Error
+    }
+}
+
+/// A flag to cancel a parse from another thread, see
+/// [`ParseDriver::set_cancellation_token`](ParseDriver::set_cancellation_token).
+///
+/// Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a new, not yet cancelled token.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Cancels all parses using this token.
+    #[inline]
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if [`cancel`](CancellationToken::cancel) was called.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A limit on the work done by a parse, see [`ParseDriver::set_budget`](ParseDriver::set_budget).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Budget {
+    /// The maximum number of parser invocations made by the combinators checking the budget,
+    /// see [`ParseDriver::set_budget`](ParseDriver::set_budget)
+    Invocations(usize),
+    /// The maximum number of elements (bytes, for byte slices) examined by the parsers
+    /// invoked by the combinators checking the budget
+    ///
+    /// Every invocation examines the elements between its start position and the furthest
+    /// position reached by it, including the positions reached by the invocations nested in it.
+    /// That way, failures rewinding the position are charged the elements examined before
+    /// failing as well. Elements are charged once, to the innermost invocation examining them,
+    /// unless they are examined again, e.g. by the next branch of an
+    /// [`Alternate`](crate::combinators::Alternate).
+    Bytes(usize),
+}
+
+/// The failures at the furthest offset into the input, as tracked by
+/// [`ParseDriver::track_furthest_failure`](ParseDriver::track_furthest_failure).
+///
+/// Displays as a message like "expected `,`, `]` or number at offset 42", or
+/// "unexpected input at offset 42" if none of the errors described what was expected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FurthestFailure {
+    /// The offset of the failures
+    pub offset: usize,
+    /// The number of failures at `offset`
+    pub failures: usize,
+    /// The deduplicated expectations of all failures at `offset` that describe them (see
+    /// [`Recoverable::as_expectation`](crate::Recoverable::as_expectation)),
+    /// in the order they were recorded
+    pub expected: Vec<Expected>,
+}
+
+impl fmt::Display for FurthestFailure {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_expected(&self.expected, self.offset, f)
+    }
 }
 
 impl ParseDriver<()> {
@@ -21,7 +156,7 @@ impl ParseDriver<()> {
 impl Default for ParseDriver<()> {
     #[inline]
     fn default() -> Self {
-        Self { state: () }
+        Self::with_state(())
     }
 }
 
@@ -29,7 +164,517 @@ impl<S> ParseDriver<S> {
     /// Creates a new `ParseDriver` with `state` as initial state.
     #[inline]
     pub fn with_state(state: S) -> Self {
-        Self { state }
+        Self {
+            state,
+            context: Vec::new(),
+            furthest_failure: None,
+            trace: None,
+            profile: None,
+            budget: None,
+            spent: 0,
+            reached: 0,
+            charged: 0,
+            depth: 0,
+            max_depth: None,
+            cancellation: None,
+            no_progress_policy: NoProgressPolicy::default(),
+            ambiguities: None,
+            clone_state: None,
+        }
+    }
+
+    /// Starts detecting ambiguities: [`Alternate`](crate::combinators::Alternate)s keep running
+    /// their branches after the first successful one, recording an
+    /// [`Ambiguity`](crate::combinators::Ambiguity) whenever more than one branch matches, or
+    /// a branch after the first successful one fails irrecoverably.
+    ///
+    /// Meant for debugging grammars, as it runs parsers that would be skipped otherwise.
+    /// Their side effects are undone afterwards: the parser [`state`](ParseDriver::state) is
+    /// cloned before and restored after them, and neither the budget, recorded failures, the
+    /// trace nor the profile are affected by them. Restarts detection if it was already enabled.
+    #[inline]
+    pub fn detect_ambiguities(&mut self)
+    where
+        S: Clone,
+    {
+        self.ambiguities = Some(Vec::new());
+        self.clone_state = Some(S::clone);
+    }
+
+    /// Returns `true` if ambiguities are being detected.
+    #[inline]
+    pub fn detects_ambiguities(&self) -> bool {
+        self.ambiguities.is_some()
+    }
+
+    /// Returns the detected ambiguities, in the order their alternates finished,
+    /// and stops detecting them.
+    ///
+    /// Returns `None` if detection was not enabled.
+    #[inline]
+    pub fn take_ambiguities(&mut self) -> Option<Vec<Ambiguity>> {
+        self.clone_state = None;
+        self.ambiguities.take()
+    }
+
+    /// Records an ambiguity, if ambiguities are being detected.
+    pub(crate) fn record_ambiguity(&mut self, ambiguity: Ambiguity) {
+        if let Some(ambiguities) = &mut self.ambiguities {
+            ambiguities.push(ambiguity);
+        }
+    }
+
+    /// Saves the parser state, budget, recorded failures, ambiguities, trace and profile, to
+    /// undo the side effects of branches that only run to detect ambiguities via
+    /// [`restore_side_effects`](ParseDriver::restore_side_effects).
+    pub(crate) fn save_side_effects(&self) -> SideEffects<S> {
+        SideEffects {
+            state: self.clone_state.map(|clone| clone(&self.state)),
+            furthest_failure: self.furthest_failure.clone(),
+            ambiguities: self.ambiguities.as_ref().map(Vec::len),
+            spent: self.spent,
+            reached: self.reached,
+            charged: self.charged,
+            trace: self.trace.as_ref().map(|trace| trace.nodes().len()),
+            profile: self.profile.clone(),
+        }
+    }
+
+    /// Restores the side effects saved via
+    /// [`save_side_effects`](ParseDriver::save_side_effects).
+    pub(crate) fn restore_side_effects(&mut self, saved: SideEffects<S>) {
+        if let Some(state) = saved.state {
+            self.state = state;
+        }
+        self.furthest_failure = saved.furthest_failure;
+        if let (Some(ambiguities), Some(len)) = (&mut self.ambiguities, saved.ambiguities) {
+            ambiguities.truncate(len);
+        }
+        self.spent = saved.spent;
+        self.reached = saved.reached;
+        self.charged = saved.charged;
+        if let (Some(trace), Some(len)) = (&mut self.trace, saved.trace) {
+            trace.truncate(len);
+        }
+        self.profile = saved.profile;
+    }
+
+    /// Sets how the repetition combinators react to a parser that succeeds without progressing.
+    ///
+    /// Defaults to [`NoProgressPolicy::Error`](NoProgressPolicy::Error).
+    #[inline]
+    pub fn set_no_progress_policy(&mut self, policy: NoProgressPolicy) {
+        self.no_progress_policy = policy;
+    }
+
+    /// Returns the policy set via [`set_no_progress_policy`](ParseDriver::set_no_progress_policy).
+    #[inline]
+    pub fn no_progress_policy(&self) -> NoProgressPolicy {
+        self.no_progress_policy
+    }
+
+    /// Handles a parser repeated by `combinator` that did not progress at `pos`, according to
+    /// the policy, returning the error to fail with.
+    ///
+    /// Returns `None` if it should be ignored.
+    pub(crate) fn no_progress<P: Pos, E: Recoverable>(
+        &self,
+        combinator: &'static str,
+        pos: P,
+    ) -> Option<E> {
+        let err = DriverError::NoProgress {
+            offset: pos.offset(),
+            combinator,
+        };
+
+        match self.no_progress_policy {
+            NoProgressPolicy::Ignore => None,
+            NoProgressPolicy::Panic if !cfg!(feature = "panic_free") => panic!("{}", err),
+            NoProgressPolicy::Error | NoProgressPolicy::Panic => Some(E::from_driver_error(err)),
+        }
+    }
+
+    /// Makes the following parses cancellable via `token`.
+    ///
+    /// Once the token is cancelled, [`Alternate`](crate::combinators::Alternate),
+    /// [`count`](crate::combinators::count),
+    /// [`zero_or_more`](crate::combinators::zero_or_more) and
+    /// [`one_or_more`](crate::combinators::one_or_more) (and their variants) fail with
+    /// [`DriverError::Cancelled`](crate::DriverError::Cancelled) before running their next
+    /// parser, converted via
+    /// [`Recoverable::from_driver_error`](crate::Recoverable::from_driver_error).
+    ///
+    /// The driver is `Send` as long as its state is, so the parse can run on another thread
+    /// than the one cancelling it.
+    #[inline]
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = Some(token);
+    }
+
+    /// Removes the cancellation token, returning it.
+    #[inline]
+    pub fn clear_cancellation_token(&mut self) -> Option<CancellationToken> {
+        self.cancellation.take()
+    }
+
+    /// Returns `true` if the parse was cancelled via the cancellation token.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        matches!(&self.cancellation, Some(token) if token.is_cancelled())
+    }
+
+    /// Returns the error to fail with at `pos` if the parse was cancelled.
+    pub(crate) fn cancelled<P: Pos, E: Recoverable>(&self, pos: P) -> Option<E> {
+        if self.is_cancelled() {
+            Some(E::from_driver_error(DriverError::Cancelled {
+                offset: pos.offset(),
+            }))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the current nesting depth, i.e. the number of
+    /// [`nested`](crate::combinators::nested) parsers currently running.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Limits the nesting depth of [`nested`](crate::combinators::nested) parsers to `max_depth`.
+    #[inline]
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = Some(max_depth);
+    }
+
+    /// Removes the nesting depth limit.
+    #[inline]
+    pub fn clear_max_depth(&mut self) {
+        self.max_depth = None;
+    }
+
+    /// Returns the nesting depth limit set via [`set_max_depth`](ParseDriver::set_max_depth).
+    #[inline]
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Limits the work of the following parses to `budget`, starting with the full budget.
+    ///
+    /// Once the budget is exhausted, [`Alternate`](crate::combinators::Alternate),
+    /// [`optional`](crate::combinators::optional), [`count`](crate::combinators::count),
+    /// [`zero_or_more`](crate::combinators::zero_or_more) and
+    /// [`one_or_more`](crate::combinators::one_or_more) (and their variants),
+    /// [`nested`](crate::combinators::nested) and
+    /// [`recover_with`](crate::combinators::recover_with) fail with
+    /// [`DriverError::BudgetExhausted`](crate::DriverError::BudgetExhausted) instead of
+    /// running their parsers, converted via
+    /// [`Recoverable::from_driver_error`](crate::Recoverable::from_driver_error).
+    ///
+    /// [`rule`](crate::combinators::rule) and [`context`](crate::combinators::context) only
+    /// label their parsers, so they neither check the budget nor are charged to it.
+    #[inline]
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.budget = Some(budget);
+        self.spent = 0;
+    }
+
+    /// Removes the budget, not limiting the following parses anymore.
+    #[inline]
+    pub fn clear_budget(&mut self) {
+        self.budget = None;
+        self.spent = 0;
+    }
+
+    /// Returns the budget set via [`set_budget`](ParseDriver::set_budget).
+    #[inline]
+    pub fn budget(&self) -> Option<Budget> {
+        self.budget
+    }
+
+    /// Returns the remaining invocations or bytes of the budget, or `None` if there is none.
+    #[inline]
+    pub fn remaining_budget(&self) -> Option<usize> {
+        self.budget.map(|budget| match budget {
+            Budget::Invocations(max) | Budget::Bytes(max) => max.saturating_sub(self.spent),
+        })
+    }
+
+    /// Refills the budget, e.g. before parsing the next message.
+    #[inline]
+    pub fn reset_budget(&mut self) {
+        self.spent = 0;
+    }
+
+    /// Runs `parser` at `pos` if the budget allows it, charging the invocation to it.
+    ///
+    /// Exhaustion can't go unnoticed, as every error type represents
+    /// [`DriverError::BudgetExhausted`](crate::DriverError::BudgetExhausted).
+    pub(crate) fn budgeted<P, T, E, F>(&mut self, pos: P, parser: F) -> Progress<P, T, E>
+    where
+        P: Pos,
+        E: Recoverable,
+        F: FnOnce(&mut Self, P) -> Progress<P, T, E>,
+    {
+        let budget = match self.budget {
+            Some(budget) => budget,
+            None => return parser(self, pos),
+        };
+
+        if self.remaining_budget() == Some(0) {
+            let err = DriverError::BudgetExhausted {
+                offset: pos.offset(),
+            };
+            return Progress::failure(pos, E::from_driver_error(err));
+        }
+
+        let outer_reached = mem::replace(&mut self.reached, pos.offset());
+        let outer_charged = mem::replace(&mut self.charged, 0);
+        let progress = parser(self, pos);
+        let reached = self.reached.max(progress.pos.offset());
+        self.reached = outer_reached.max(reached);
+
+        // elements already charged to nested invocations aren't charged again
+        let examined = reached.saturating_sub(pos.offset());
+        let cost = match budget {
+            Budget::Invocations(..) => 1,
+            Budget::Bytes(..) => examined.saturating_sub(self.charged),
+        };
+        self.charged = outer_charged.saturating_add(examined.max(self.charged));
+        self.spent = self.spent.saturating_add(cost);
+        progress
+    }
+
+    /// Starts collecting a [`Profile`](crate::profile::Profile) of all named parsers,
+    /// see [`rule`](crate::combinators::rule).
+    ///
+    /// Restarts profiling if it was already enabled.
+    #[inline]
+    pub fn enable_profiling(&mut self) {
+        self.profile = Some(Profile::default());
+    }
+
+    /// Returns the collected profile and stops profiling.
+    ///
+    /// Returns `None` if profiling was not enabled.
+    #[inline]
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        self.profile.take()
+    }
+
+    /// Returns the start time of a rule if profiling is enabled.
+    pub(crate) fn profile_start(&self) -> Option<Instant> {
+        self.profile.as_ref().map(|_| Instant::now())
+    }
+
+    /// Records a run of the rule `name` started at `pos` and `started`,
+    /// see [`profile_start`](ParseDriver::profile_start).
+    pub(crate) fn profile_end<P, T, E>(
+        &mut self,
+        name: &'static str,
+        pos: P,
+        progress: &Progress<P, T, E>,
+        started: Option<Instant>,
+    ) where
+        P: Pos,
+    {
+        if let (Some(profile), Some(started)) = (&mut self.profile, started) {
+            profile.record(
+                name,
+                pos.offset(),
+                progress.pos.offset(),
+                progress.is_ok(),
+                started.elapsed(),
+            );
+        }
+    }
+
+    /// Starts recording a [`Trace`](crate::trace::Trace) of all named parser invocations,
+    /// see [`rule`](crate::combinators::rule).
+    ///
+    /// [`Alternate`](crate::combinators::Alternate) branches and
+    /// [`zero_or_more`](crate::combinators::zero_or_more)/[`one_or_more`](crate::combinators::one_or_more)
+    /// iterations are recorded as well.
+    ///
+    /// Restarts recording if it was already enabled.
+    #[inline]
+    pub fn enable_trace(&mut self) {
+        self.trace = Some(Trace::default());
+    }
+
+    /// Returns the recorded trace and stops recording.
+    ///
+    /// Returns `None` if tracing was not enabled.
+    #[inline]
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.trace.take()
+    }
+
+    /// Records entering a parser at `pos` if tracing is enabled, returning the id of its
+    /// trace node.
+    ///
+    /// `name` is only called if tracing is enabled.
+    pub(crate) fn trace_enter<P, F>(&mut self, name: F, pos: P) -> Option<usize>
+    where
+        P: Pos,
+        F: FnOnce() -> Cow<'static, str>,
+    {
+        let trace = self.trace.as_mut()?;
+        Some(trace.enter(name(), pos.offset()))
+    }
+
+    /// Records exiting the parser entered via [`trace_enter`](ParseDriver::trace_enter).
+    ///
+    /// `summarize` is only called on failure, if tracing is enabled.
+    pub(crate) fn trace_exit<P, T, E, F>(
+        &mut self,
+        id: Option<usize>,
+        progress: &Progress<P, T, E>,
+        summarize: F,
+    ) where
+        P: Pos,
+        F: FnOnce(&E) -> Option<String>,
+    {
+        if let (Some(trace), Some(id)) = (&mut self.trace, id) {
+            let result = progress.status.as_ref().map(|_| ()).map_err(summarize);
+            trace.exit(id, progress.pos.offset(), result);
+        }
+    }
+
+    /// Starts tracking the furthest offset at which any parser failed, together with what
+    /// was expected there, no matter which combinator discarded the failures.
+    ///
+    /// Failures are recorded by [`optional`](crate::combinators::optional),
+    /// [`zero_or_more`](crate::combinators::zero_or_more),
+    /// [`one_or_more`](crate::combinators::one_or_more) and
+    /// [`Alternate`](crate::combinators::Alternate) whenever they recover from them,
+    /// or manually via [`record_failure`](ParseDriver::record_failure).
+    /// Retrieve them via [`take_furthest_failure`](ParseDriver::take_furthest_failure).
+    ///
+    /// Only the offset of a failure is tracked, unless its error describes what was expected
+    /// via [`Recoverable::as_expectation`](crate::Recoverable::as_expectation).
+    ///
+    /// Restarts tracking if it was already enabled.
+    #[inline]
+    pub fn track_furthest_failure(&mut self) {
+        self.furthest_failure = Some(FurthestFailure {
+            offset: 0,
+            failures: 0,
+            expected: Vec::new(),
+        });
+    }
+
+    /// Returns `true` if the furthest failure is being tracked.
+    #[inline]
+    pub fn tracks_furthest_failure(&self) -> bool {
+        self.furthest_failure.is_some()
+    }
+
+    /// Records a failure for the furthest failure tracking, if it is enabled.
+    ///
+    /// The failure is kept if it happened at least as far into the input as all previously
+    /// recorded failures. Failures further into the input discard all previous ones.
+    ///
+    /// Record the error of a failed parse before calling
+    /// [`take_furthest_failure`](ParseDriver::take_furthest_failure), as it may be the furthest one.
+    #[inline]
+    pub fn record_failure<P, E>(&mut self, pos: P, error: &E)
+    where
+        P: Pos,
+        E: Recoverable,
+    {
+        if let Some(failure) = &mut self.furthest_failure {
+            let offset = pos.offset();
+            if offset > failure.offset || failure.failures == 0 {
+                failure.offset = offset;
+                failure.failures = 0;
+                failure.expected.clear();
+            }
+
+            if offset == failure.offset {
+                failure.failures += 1;
+                if let Some(expectation) = error.as_expectation() {
+                    for expected in expectation.expected() {
+                        if !failure.expected.contains(&expected) {
+                            failure.expected.push(expected);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the furthest failure, and restarts tracking.
+    ///
+    /// Returns `None` if tracking is not enabled, or if no failure was recorded.
+    #[inline]
+    pub fn take_furthest_failure(&mut self) -> Option<FurthestFailure> {
+        let failure = self.furthest_failure.as_mut()?;
+        if failure.failures == 0 {
+            return None;
+        }
+
+        Some(mem::replace(
+            failure,
+            FurthestFailure {
+                offset: 0,
+                failures: 0,
+                expected: Vec::new(),
+            },
+        ))
+    }
+
+    /// Returns the labels of all [`context`](ParseDriver::context)s
+    /// that are currently being parsed, outermost context first.
+    #[inline]
+    pub fn context_stack(&self) -> &[ContextFrame] {
+        &self.context
+    }
+
+    /// Runs the specified `parser` with `label` pushed onto the context stack,
+    /// wrapping its error into a [`Contextual`](crate::combinators::Contextual) on failure.
+    ///
+    /// See [`context`](crate::combinators::context).
+    #[inline]
+    pub fn context<P, T, E, E2, F>(
+        &mut self,
+        label: &'static str,
+        pos: P,
+        parser: F,
+    ) -> Progress<P, T, Contextual<E2>>
+    where
+        P: Pos,
+        E: Into<Contextual<E2>>,
+        F: FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
+    {
+        context(label, parser)(self, pos)
+    }
+
+    /// Runs the specified `parser` as a named rule, recording it in the trace if enabled.
+    ///
+    /// See [`rule`](crate::combinators::rule).
+    #[inline]
+    pub fn rule<P, T, E, F>(&mut self, name: &'static str, pos: P, parser: F) -> Progress<P, T, E>
+    where
+        P: Pos,
+        E: Debug,
+        F: FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
+    {
+        rule(name, parser)(self, pos)
+    }
+
+    /// Runs the specified `parser` one nesting level deeper, failing if that exceeds the
+    /// maximum depth.
+    ///
+    /// See [`nested`](crate::combinators::nested).
+    #[inline]
+    pub fn nested<P, T, E, F>(&mut self, pos: P, parser: F) -> Progress<P, T, E>
+    where
+        P: Pos,
+        E: Recoverable,
+        F: FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
+    {
+        nested(parser)(self, pos)
     }
 
     /// Wraps the specified `parser`, making it optional.
@@ -47,6 +692,26 @@ impl<S> ParseDriver<S> {
         optional(parser)(self, pos)
     }
 
+    /// Runs the specified `parser`, recovering from malformed input by skipping
+    /// to a synchronization point via `sync`.
+    ///
+    /// See [`recover_with`](crate::combinators::recover_with).
+    #[inline]
+    pub fn recover_with<P, T, E, F, T2, E2, Fs>(
+        &mut self,
+        pos: P,
+        parser: F,
+        sync: Fs,
+    ) -> Progress<P, Recovered<T, E>, E>
+    where
+        P: Pos,
+        E: Recoverable,
+        F: FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T, E>,
+        Fs: FnOnce(&mut ParseDriver<S>, P) -> Progress<P, T2, E2>,
+    {
+        recover_with(parser, sync)(self, pos)
+    }
+
     /// Tries all parsers supplied via [`one`](crate::combinators::Alternate::one), in order,
     /// until one matches.
     ///
@@ -85,3 +750,374 @@ impl<S> ParseDriver<S> {
         Alternate::new(self, pos, error_accumulator)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::combinators::{count, zero_or_more};
+    use crate::slice::BytePos;
+    use crate::testing::fixtures::{byte, Error};
+    use crate::{
+        Budget, CancellationToken, DriverError, ErrorKind, ParseDriver, Progress, Recoverable,
+    };
+
+    fn pair<'a>(
+        first: u8,
+        second: u8,
+    ) -> impl Fn(&mut ParseDriver, BytePos<'a>) -> Progress<BytePos<'a>, u8, Error> {
+        move |pd, pos| {
+            let (pos, _) = pahs!(byte(first)(pd, pos));
+            byte(second)(pd, pos)
+        }
+    }
+
+    #[test]
+    fn drivers_are_send_and_sync() {
+        fn is_send_and_sync<T: Send + Sync>() {}
+
+        is_send_and_sync::<ParseDriver>();
+    }
+
+    #[test]
+    fn it_tracks_the_furthest_failure_through_combinators() {
+        let pd = &mut ParseDriver::new();
+        pd.track_furthest_failure();
+
+        // items are `a` or `bc`, terminated by `;`
+        let pos = BytePos::new(b"abx;");
+        let (pos, items) = zero_or_more(|pd, pos| {
+            pd.alternate(pos)
+                .one(byte(b'a'))
+                .one(pair(b'b', b'c'))
+                .finish()
+        })(pd, pos)
+        .unwrap();
+        assert_eq!((pos.offset, items), (1, vec![b'a']));
+
+        let (pos, err) = byte(b';')(pd, pos).unwrap_err();
+        pd.record_failure(pos, &err);
+
+        let failure = pd.take_furthest_failure().unwrap();
+        assert_eq!(failure.offset, 2);
+        assert_eq!(failure.failures, 1);
+        assert_eq!(failure.to_string(), "expected `c` at offset 2");
+
+        // tracking restarts
+        assert!(pd.take_furthest_failure().is_none());
+        assert!(pd.tracks_furthest_failure());
+    }
+
+    #[test]
+    fn it_tracks_failures_of_discarded_branches() {
+        let pd = &mut ParseDriver::new();
+        let pos = BytePos::new(b"bc");
+
+        let alternate = |pd: &mut ParseDriver| {
+            pd.alternate(pos)
+                .one(pair(b'b', b'x'))
+                .one(pair(b'b', b'y'))
+                .one(byte(b'a'))
+                .one(byte(b'b'))
+                .finish()
+                .unwrap()
+        };
+
+        // nothing is tracked by default
+        let (new_pos, _) = alternate(pd);
+        assert_eq!(new_pos.offset, 1);
+        assert!(pd.take_furthest_failure().is_none());
+
+        pd.track_furthest_failure();
+        let (new_pos, _) = alternate(pd);
+        assert_eq!(new_pos.offset, 1);
+
+        let failure = pd.take_furthest_failure().unwrap();
+        assert_eq!(failure.offset, 1);
+        assert_eq!(failure.failures, 2);
+        assert_eq!(failure.to_string(), "expected `x` or `y` at offset 1");
+    }
+
+    #[test]
+    fn it_tracks_failures_without_expectations() {
+        #[derive(Debug)]
+        enum Unexpected<'a> {
+            Input(&'a [u8]),
+            Driver,
+        }
+
+        impl Recoverable for Unexpected<'_> {
+            fn kind(&self) -> ErrorKind {
+                match self {
+                    Unexpected::Input([]) => ErrorKind::Incomplete,
+                    Unexpected::Input(..) => ErrorKind::Backtrack,
+                    Unexpected::Driver => ErrorKind::Fatal,
+                }
+            }
+
+            fn from_driver_error(_err: DriverError) -> Self {
+                Unexpected::Driver
+            }
+        }
+
+        let pd = &mut ParseDriver::new();
+        pd.track_furthest_failure();
+
+        // errors borrowing the input work with all combinators
+        let (pos, items) = zero_or_more(|pd, pos: BytePos<'_>| {
+            byte(b'a')(pd, pos).map_err(|_| Unexpected::Input(pos.s))
+        })(pd, BytePos::new(b"aab"))
+        .unwrap();
+        assert_eq!((pos.offset, items), (2, vec![b'a', b'a']));
+
+        let failure = pd.take_furthest_failure().unwrap();
+        assert_eq!((failure.offset, failure.failures), (2, 1));
+        assert!(failure.expected.is_empty());
+        assert_eq!(failure.to_string(), "unexpected input at offset 2");
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Limited {
+        Expected(char),
+        BudgetExhausted(usize),
+        Cancelled(usize),
+        Driver(DriverError),
+    }
+
+    impl From<Error> for Limited {
+        fn from(err: Error) -> Self {
+            match err {
+                Error::Expected(c) => Limited::Expected(c),
+                Error::Driver(err) => Limited::from_driver_error(err),
+            }
+        }
+    }
+
+    impl Recoverable for Limited {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                Limited::Expected(..) => ErrorKind::Backtrack,
+                Limited::BudgetExhausted(..) | Limited::Cancelled(..) | Limited::Driver(..) => {
+                    ErrorKind::Fatal
+                }
+            }
+        }
+
+        fn from_driver_error(err: DriverError) -> Self {
+            match err {
+                DriverError::BudgetExhausted { offset } => Limited::BudgetExhausted(offset),
+                DriverError::Cancelled { offset } => Limited::Cancelled(offset),
+                err => Limited::Driver(err),
+            }
+        }
+    }
+
+    fn limited<'a>(
+        c: u8,
+    ) -> impl Fn(&mut ParseDriver, BytePos<'a>) -> Progress<BytePos<'a>, u8, Limited> {
+        move |pd, pos| byte(c)(pd, pos).map_err(Limited::from)
+    }
+
+    #[test]
+    fn it_limits_invocations() {
+        let pd = &mut ParseDriver::new();
+        pd.set_budget(Budget::Invocations(3));
+        assert_eq!(pd.remaining_budget(), Some(3));
+
+        // the third invocation fails, ending the repetition
+        let (pos, items) = zero_or_more(limited(b'a'))(pd, BytePos::new(b"aab")).unwrap();
+        assert_eq!((pos.offset, items), (2, vec![b'a', b'a']));
+        assert_eq!(pd.remaining_budget(), Some(0));
+
+        let (pos, err) = zero_or_more(limited(b'a'))(pd, BytePos::new(b"aab")).unwrap_err();
+        assert_eq!(pos.offset, 0);
+        assert_eq!(err, Limited::BudgetExhausted(0));
+
+        let (pos, err) = pd
+            .alternate(BytePos::new(b"b"))
+            .one(limited(b'a'))
+            .one(limited(b'b'))
+            .finish()
+            .unwrap_err();
+        assert_eq!(pos.offset, 0);
+        assert_eq!(err, Limited::BudgetExhausted(0));
+        assert!(!err.recoverable());
+
+        let (_, err) = pd.optional(BytePos::new(b"b"), limited(b'a')).unwrap_err();
+        assert_eq!(err, Limited::BudgetExhausted(0));
+
+        pd.reset_budget();
+        assert_eq!(pd.remaining_budget(), Some(3));
+        let (pos, _) = zero_or_more(limited(b'a'))(pd, BytePos::new(b"aab")).unwrap();
+        assert_eq!(pos.offset, 2);
+
+        pd.clear_budget();
+        assert_eq!(pd.budget(), None);
+        assert_eq!(pd.remaining_budget(), None);
+        let (pos, _) = zero_or_more(limited(b'a'))(pd, BytePos::new(b"aaaab")).unwrap();
+        assert_eq!(pos.offset, 4);
+    }
+
+    #[test]
+    fn it_limits_examined_bytes() {
+        let pd = &mut ParseDriver::new();
+        pd.set_budget(Budget::Bytes(3));
+
+        let pair = |first, second| {
+            move |pd: &mut ParseDriver, pos| pair(first, second)(pd, pos).map_err(Limited::from)
+        };
+
+        // the failed `ax` branch is charged the byte it examined
+        let (pos, _) = pd
+            .alternate(BytePos::new(b"ab"))
+            .one(pair(b'a', b'x'))
+            .one(pair(b'a', b'b'))
+            .finish()
+            .unwrap();
+        assert_eq!(pos.offset, 2);
+        assert_eq!(pd.remaining_budget(), Some(0));
+
+        let (_, err) = pd.optional(pos, limited(b'a')).unwrap_err();
+        assert_eq!(err, Limited::BudgetExhausted(2));
+    }
+
+    #[test]
+    fn it_charges_the_bytes_examined_by_rewinding_failures() {
+        let pd = &mut ParseDriver::new();
+        pd.set_budget(Budget::Bytes(10));
+
+        // examines `aaa`, then fails at the `x`, rewinding to the start
+        let rewinding = |pd: &mut ParseDriver, start: BytePos<'static>| {
+            let (pos, _) = pahs!(zero_or_more(limited(b'a'))(pd, start));
+            match limited(b'x')(pd, pos).status {
+                Ok(..) => pos.success(()),
+                Err(err) => start.failure(err),
+            }
+        };
+
+        // charged 1 per `a`, which isn't charged again to the failed `optional` parser
+        let (pos, val) = pd.optional(BytePos::new(b"aaab"), rewinding).unwrap();
+        assert_eq!((pos.offset, val), (0, None));
+        assert_eq!(pd.remaining_budget(), Some(7));
+
+        // examining the `a`s again is charged again
+        let (pos, _) = zero_or_more(limited(b'a'))(pd, pos).unwrap();
+        assert_eq!(pos.offset, 3);
+        assert_eq!(pd.remaining_budget(), Some(4));
+    }
+
+    #[test]
+    fn it_reports_exhausted_budgets_with_any_error_type() {
+        use crate::slice::text::{int, NumberError};
+
+        let pd = &mut ParseDriver::new();
+        pd.set_budget(Budget::Invocations(2));
+
+        let (_, n) = count(1, int::<u8, _>)(pd, BytePos::new(b"123")).unwrap();
+        assert_eq!(n, &[123]);
+
+        let (_, err) = count(2, int::<u8, _>)(pd, BytePos::new(b"123")).unwrap_err();
+        assert_eq!(
+            err,
+            NumberError::Driver {
+                source: DriverError::BudgetExhausted { offset: 3 }
+            }
+        );
+        assert!(!err.recoverable());
+
+        #[derive(Debug, PartialEq)]
+        enum Borrowed<'a> {
+            Unexpected(&'a [u8]),
+            Driver(DriverError),
+        }
+
+        impl Recoverable for Borrowed<'_> {
+            fn kind(&self) -> ErrorKind {
+                match self {
+                    Borrowed::Unexpected(..) => ErrorKind::Backtrack,
+                    Borrowed::Driver(..) => ErrorKind::Fatal,
+                }
+            }
+
+            fn from_driver_error(err: DriverError) -> Self {
+                Borrowed::Driver(err)
+            }
+        }
+
+        pd.reset_budget();
+        let (_, err) = zero_or_more(|pd, pos: BytePos<'_>| {
+            byte(b'a')(pd, pos).map_err(|_| Borrowed::Unexpected(pos.s))
+        })(pd, BytePos::new(b"aaa"))
+        .unwrap_err();
+        assert_eq!(
+            err,
+            Borrowed::Driver(DriverError::BudgetExhausted { offset: 2 })
+        );
+    }
+
+    #[test]
+    fn it_cancels_parses_running_on_other_threads() {
+        use std::sync::mpsc;
+        use std::thread;
+
+        let token = CancellationToken::new();
+        let mut pd = ParseDriver::new();
+        pd.set_cancellation_token(token.clone());
+
+        let (started_tx, started_rx) = mpsc::channel();
+        let (cancelled_tx, cancelled_rx) = mpsc::channel();
+        let parse = thread::spawn(move || {
+            let input = [b'a'; 4];
+            let (_, err) = zero_or_more(|pd: &mut ParseDriver, pos: BytePos<'_>| {
+                if pos.offset == 1 {
+                    started_tx.send(()).unwrap();
+                    cancelled_rx.recv().unwrap();
+                }
+                limited(b'a')(pd, pos)
+            })(&mut pd, BytePos::new(&input))
+            .unwrap_err();
+            err
+        });
+
+        started_rx.recv().unwrap();
+        token.cancel();
+        cancelled_tx.send(()).unwrap();
+        assert_eq!(parse.join().unwrap(), Limited::Cancelled(2));
+    }
+
+    #[test]
+    fn it_stops_cancelled_parses() {
+        let pd = &mut ParseDriver::new();
+        let token = CancellationToken::new();
+        pd.set_cancellation_token(token.clone());
+        assert!(!pd.is_cancelled());
+
+        let (pos, err) = zero_or_more(|pd: &mut ParseDriver, pos| -> Progress<_, _, Limited> {
+            let (pos, b) = pahs!(limited(b'a')(pd, pos));
+            if pos.offset == 2 {
+                token.cancel();
+            }
+            Progress::success(pos, b)
+        })(pd, BytePos::new(b"aaaa"))
+        .unwrap_err();
+        assert_eq!(pos.offset, 0);
+        assert_eq!(err, Limited::Cancelled(2));
+        assert!(pd.is_cancelled());
+
+        let (_, err) = pd
+            .alternate(BytePos::new(b"a"))
+            .one(limited(b'a'))
+            .finish()
+            .unwrap_err();
+        assert_eq!(err, Limited::Cancelled(0));
+
+        // cancellation from another thread
+        let pd = &mut ParseDriver::new();
+        let token = CancellationToken::new();
+        pd.set_cancellation_token(token.clone());
+        std::thread::spawn(move || token.cancel()).join().unwrap();
+        assert!(pd.is_cancelled());
+
+        assert!(pd.clear_cancellation_token().unwrap().is_cancelled());
+        assert!(!pd.is_cancelled());
+        assert!(zero_or_more(limited(b'a'))(pd, BytePos::new(b"aaaa")).is_ok());
+    }
+}
@@ -2,6 +2,12 @@
 pub trait Pos: Eq + Copy {
     /// The initial position
     fn zero() -> Self;
+
+    /// The absolute offset of this position from the beginning of the input.
+    ///
+    /// Used for error reporting, furthest failure tracking, budgets, traces and profiles,
+    /// so it has to grow as the position advances.
+    fn offset(&self) -> usize;
 }
 
 impl Pos for usize {
@@ -9,4 +15,9 @@ impl Pos for usize {
     fn zero() -> Self {
         0
     }
+
+    #[inline]
+    fn offset(&self) -> usize {
+        *self
+    }
 }
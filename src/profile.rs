@@ -0,0 +1,168 @@
+//! Collects per-rule statistics for finding slow rules and backtracking hotspots.
+//!
+//! Enable profiling via [`ParseDriver::enable_profiling`](crate::ParseDriver::enable_profiling),
+//! name parsers via [`rule`](crate::combinators::rule), and retrieve the collected
+//! [`Profile`](Profile) via [`ParseDriver::take_profile`](crate::ParseDriver::take_profile)
+//! after the parse.
+
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::time::Duration;
+
+/// Statistics of a single rule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuleStats {
+    /// How often the rule was run
+    pub invocations: usize,
+    /// How often the rule succeeded
+    pub successes: usize,
+    /// How often the rule failed
+    pub failures: usize,
+    /// The total number of elements consumed by successful runs
+    pub consumed: usize,
+    /// The total wall time spent in the rule, including nested rules
+    pub time: Duration,
+}
+
+/// A rule that was attempted repeatedly at the same offset, usually because of backtracking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotspot {
+    /// The name of the rule
+    pub rule: &'static str,
+    /// The offset the rule was attempted at
+    pub offset: usize,
+    /// How often the rule was attempted at `offset`
+    pub attempts: usize,
+}
+
+/// The statistics collected while profiling.
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    rules: HashMap<&'static str, RuleStats>,
+    attempts: HashMap<(&'static str, usize), usize>,
+}
+
+impl Profile {
+    /// Returns the statistics of all rules, sorted by descending total time.
+    #[inline]
+    pub fn rules(&self) -> Vec<(&'static str, RuleStats)> {
+        let mut rules = self
+            .rules
+            .iter()
+            .map(|(&name, &stats)| (name, stats))
+            .collect::<Vec<_>>();
+        rules.sort_by(|(a_name, a), (b_name, b)| b.time.cmp(&a.time).then(a_name.cmp(b_name)));
+        rules
+    }
+
+    /// Returns the statistics of the rule named `name`, if it was run.
+    #[inline]
+    pub fn rule(&self, name: &str) -> Option<RuleStats> {
+        self.rules.get(name).copied()
+    }
+
+    /// Returns all rules that were attempted more than once at the same offset,
+    /// sorted by descending number of attempts, then by offset.
+    #[inline]
+    pub fn hotspots(&self) -> Vec<Hotspot> {
+        let mut hotspots = self
+            .attempts
+            .iter()
+            .filter(|(_, &attempts)| attempts > 1)
+            .map(|(&(rule, offset), &attempts)| Hotspot {
+                rule,
+                offset,
+                attempts,
+            })
+            .collect::<Vec<_>>();
+        hotspots.sort_by(|a, b| {
+            b.attempts
+                .cmp(&a.attempts)
+                .then(a.offset.cmp(&b.offset))
+                .then(a.rule.cmp(b.rule))
+        });
+        hotspots
+    }
+
+    /// Renders a report table of all rules, sorted by descending total time,
+    /// followed by the backtracking hotspots.
+    #[inline]
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        // writing to a `String` can't fail
+        self.write_report(&mut out).unwrap();
+        out
+    }
+
+    fn write_report(&self, out: &mut String) -> fmt::Result {
+        let rules = self.rules();
+        let width = rules
+            .iter()
+            .map(|(name, _)| name.len())
+            .chain(Some("rule".len()))
+            .max()
+            .unwrap_or(0);
+
+        writeln!(
+            out,
+            "{:w$} {:>8} {:>8} {:>8} {:>10} {:>12}",
+            "rule",
+            "calls",
+            "ok",
+            "failed",
+            "consumed",
+            "time",
+            w = width
+        )?;
+        for (name, stats) in &rules {
+            writeln!(
+                out,
+                "{:w$} {:>8} {:>8} {:>8} {:>10} {:>12}",
+                name,
+                stats.invocations,
+                stats.successes,
+                stats.failures,
+                stats.consumed,
+                format!("{:?}", stats.time),
+                w = width
+            )?;
+        }
+
+        let hotspots = self.hotspots();
+        if !hotspots.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "rules attempted repeatedly at the same offset:")?;
+            for hotspot in &hotspots {
+                writeln!(
+                    out,
+                    "{} at offset {}: {} attempts",
+                    hotspot.rule, hotspot.offset, hotspot.attempts
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records a single run of the rule `name`.
+    pub(crate) fn record(
+        &mut self,
+        name: &'static str,
+        start: usize,
+        end: usize,
+        success: bool,
+        time: Duration,
+    ) {
+        let stats = self.rules.entry(name).or_default();
+        stats.invocations += 1;
+        stats.time += time;
+        if success {
+            stats.successes += 1;
+            stats.consumed += end.saturating_sub(start);
+        } else {
+            stats.failures += 1;
+        }
+
+        *self.attempts.entry((name, start)).or_default() += 1;
+    }
+}
@@ -1,3 +1,9 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::error_accumulator::Expectation;
+use crate::{DriverError, ErrorKind, Pos, Recoverable};
+
 /// Tracks the result of a parser: where it is and if it is successful.
 ///
 /// On success, some value has been parsed. On failure, nothing has
@@ -221,6 +227,16 @@ impl<P, T, E> Progress<P, T, E> {
         self.status.is_err()
     }
 
+    /// Wraps the error into a [`Located`](Located), attaching the offset of the failure.
+    #[inline]
+    pub fn located(self) -> Progress<P, T, Located<E>>
+    where
+        P: Pos,
+    {
+        let offset = self.pos.offset();
+        self.map_err(|error| Located { offset, error })
+    }
+
     /// Converts this progress into another by converting the value and error types into other ones.
     #[inline]
     pub fn to<T2, E2>(self) -> Progress<P, T2, E2>
@@ -238,6 +254,52 @@ impl<P, T, E> Progress<P, T, E> {
     }
 }
 
+/// An error together with the offset into the input at which it happened.
+///
+/// Created via [`Progress::located`](Progress::located).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Located<E> {
+    /// The offset of the failure
+    pub offset: usize,
+    /// The error
+    pub error: E,
+}
+
+impl<E: Recoverable> Recoverable for Located<E> {
+    #[inline]
+    fn kind(&self) -> ErrorKind {
+        self.error.kind()
+    }
+
+    #[inline]
+    fn as_expectation(&self) -> Option<&dyn Expectation> {
+        self.error.as_expectation()
+    }
+
+    /// Wraps the converted error, located at the offset of `err`.
+    #[inline]
+    fn from_driver_error(err: DriverError) -> Self {
+        Located {
+            offset: err.offset(),
+            error: E::from_driver_error(err),
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for Located<E> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.error, self.offset)
+    }
+}
+
+impl<E: Error + 'static> Error for Located<E> {
+    #[inline]
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
 impl<P, T, E> From<Result<(P, T), (P, E)>> for Progress<P, T, E> {
     #[inline]
     fn from(r: Result<(P, T), (P, E)>) -> Self {
@@ -280,3 +342,51 @@ impl<P, T, E> From<(P, Result<T, E>)> for Progress<P, T, E> {
         Self { pos, status }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::fmt;
+
+    use crate::slice::text::int;
+    use crate::slice::BytePos;
+    use crate::{DriverError, ErrorKind, Located, ParseDriver, Recoverable};
+
+    #[derive(Debug, PartialEq)]
+    struct MissingValue;
+
+    impl fmt::Display for MissingValue {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("missing value")
+        }
+    }
+
+    impl Recoverable for MissingValue {
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Backtrack
+        }
+
+        fn from_driver_error(_: DriverError) -> Self {
+            MissingValue
+        }
+    }
+
+    #[test]
+    fn located_errors_carry_the_failure_offset() {
+        let pd = &mut ParseDriver::new();
+        let pos = BytePos::new(b"x = y").advance_by(4);
+
+        let (_, err) = int::<u32, _>(pd, pos)
+            .map_err(|_| MissingValue)
+            .located()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            Located {
+                offset: 4,
+                error: MissingValue
+            }
+        );
+        assert!(err.recoverable());
+        assert_eq!(err.to_string(), "missing value at offset 4");
+    }
+}
@@ -1,42 +1,303 @@
 //! Parsers for slice data ([`SlicePos`](crate::slice::SlicePos)).
 
+use std::error::Error;
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
 use snafu::Snafu;
 
 pub mod num;
 mod pos;
+mod tags;
+pub mod text;
+mod view;
 
+use crate::error_accumulator::{Expectation, Expected};
 use crate::{ParseDriver, Progress};
 
 pub use self::pos::*;
+pub use self::tags::*;
+pub use self::view::*;
+
+/// Derives [`Pod`](trait@Pod) for `#[repr(C)]` structs, verifying at compile time
+/// that all fields are `Pod` and that the struct contains no padding.
+#[cfg(feature = "derive")]
+pub use pahs_derive::Pod;
 
 /// Matches the input slice against the `tag`, succeeding if both are equal.
+///
+/// An empty `tag` always matches without consuming any input. Previously, it panicked like
+/// [`SlicePos::take`](SlicePos::take). Repeating it is caught by the combinators, see
+/// [`DriverError::NoProgress`](crate::DriverError::NoProgress).
+#[allow(clippy::type_complexity)]
 #[inline]
-pub fn tag<'a, T: PartialEq, S>(
-    tag: &'a [T],
-) -> impl Fn(&mut ParseDriver<S>, SlicePos<'a, T>) -> Progress<SlicePos<'a, T>, &'a [T], TagError> + 'a
+pub fn tag<'a, T: PartialEq + Clone + 'a, S>(
+    tag: &[T],
+) -> impl Fn(&mut ParseDriver<S>, SlicePos<'a, T>) -> Progress<SlicePos<'a, T>, &'a [T], TagError<T>> + 'a
 {
-    move |_, pos| {
-        let (newpos, slice) = pahs!(pos.take(tag.len()).map_err(|_| NotEnoughData.build()));
+    let tag: Arc<[T]> = tag.into();
+    move |_, pos| match_tag(pos, &tag, |slice| slice == &tag[..])
+}
 
-        if slice == tag {
-            newpos.success(slice)
-        } else {
-            pos.failure(TagMismatch.build())
-        }
+/// Matches the input slice against the `tag`, succeeding if both are equal
+/// when ignoring ASCII case differences.
+#[inline]
+pub fn tag_no_case<'a, S>(
+    tag: &[u8],
+) -> impl Fn(&mut ParseDriver<S>, BytePos<'a>) -> Progress<BytePos<'a>, &'a [u8], TagError> + 'a {
+    let tag: Arc<[u8]> = tag.into();
+    move |_, pos| match_tag(pos, &tag, |slice| slice.eq_ignore_ascii_case(&tag))
+}
+
+pub(crate) fn match_tag<'a, T: Clone>(
+    pos: SlicePos<'a, T>,
+    tag: &Arc<[T]>,
+    matches: impl FnOnce(&[T]) -> bool,
+) -> Progress<SlicePos<'a, T>, &'a [T], TagError<T>> {
+    let (newpos, slice) = pahs!(pos
+        .try_take(tag.len())
+        .map_err(|_| TagError::NotEnoughData {
+            offset: pos.offset,
+            expected: Arc::clone(tag),
+            available: pos.s.len(),
+        }));
+
+    if matches(slice) {
+        newpos.success(slice)
+    } else {
+        pos.failure(TagError::TagMismatch {
+            offset: pos.offset,
+            expected: Arc::clone(tag),
+            found: slice.to_vec(),
+        })
     }
 }
 
-/// Errors that may happen when using [`tag`](tag).
-#[derive(Debug, Snafu, PartialEq, Eq)]
-#[snafu(visibility = "pub(crate)")]
-pub enum TagError {
-    /// The input slice was too short.
-    NotEnoughData,
+/// Errors that may happen when using [`tag`](tag) or [`tag_no_case`](tag_no_case).
+///
+/// The expected tag is shared with the parser, so only mismatching input is copied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagError<T = u8> {
+    /// The input slice was shorter than the tag.
+    NotEnoughData {
+        /// The offset at which the tag was expected
+        offset: usize,
+        /// The expected tag
+        expected: Arc<[T]>,
+        /// The number of elements left in the input slice
+        available: usize,
+    },
     /// The tag didn't match.
-    TagMismatch,
+    TagMismatch {
+        /// The offset at which the tag was expected
+        offset: usize,
+        /// The expected tag
+        expected: Arc<[T]>,
+        /// The input that was found instead, as long as the tag
+        found: Vec<T>,
+    },
+}
+
+impl<T: TagElement> fmt::Display for TagError<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagError::NotEnoughData {
+                offset,
+                expected,
+                available,
+            } => {
+                f.write_str("expected ")?;
+                T::fmt_tag(expected, f)?;
+                write!(
+                    f,
+                    " at offset {}, but only {} elements are left",
+                    offset, available
+                )
+            }
+            TagError::TagMismatch {
+                offset,
+                expected,
+                found,
+            } => {
+                f.write_str("expected ")?;
+                T::fmt_tag(expected, f)?;
+                write!(f, " at offset {}, found ", offset)?;
+                T::fmt_tag(found, f)
+            }
+        }
+    }
+}
+
+impl<T: TagElement> Error for TagError<T> {}
+
+impl Expectation for TagError {
+    #[inline]
+    fn expected(&self) -> Vec<Expected> {
+        match self {
+            TagError::NotEnoughData { expected, .. } | TagError::TagMismatch { expected, .. } => {
+                vec![Expected::Tag(expected.to_vec())]
+            }
+        }
+    }
+}
+
+/// Elements of tags, which can be displayed in error messages like the one of
+/// [`OneOfTagsError`](OneOfTagsError).
+///
+/// Bytes are displayed as escaped strings, and characters as strings. All other elements
+/// are displayed via their `Debug` output by default.
+pub trait TagElement: Debug + Sized {
+    /// Formats `tag` for error messages.
+    #[inline]
+    fn fmt_tag(tag: &[Self], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", tag)
+    }
+}
+
+impl TagElement for u8 {
+    #[inline]
+    fn fmt_tag(tag: &[Self], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}`", String::from_utf8_lossy(tag).escape_debug())
+    }
+}
+
+impl TagElement for char {
+    #[inline]
+    fn fmt_tag(tag: &[Self], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("`")?;
+        for c in tag {
+            write!(f, "{}", c.escape_debug())?;
+        }
+        f.write_str("`")
+    }
 }
 
+macro_rules! impl_tag_element {
+    ($($ty:ty)*) => {
+        $(impl TagElement for $ty {})*
+    };
+}
+
+impl_tag_element!(
+    u16 u32 u64 u128 usize
+    i8 i16 i32 i64 i128 isize
+);
+
 /// The input slice was too short.
 #[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotEnoughDataError {
+    /// The offset at which the data was needed
+    pub offset: usize,
+    /// The number of elements that were needed
+    pub needed: usize,
+    /// The number of elements that were left in the input slice
+    pub available: usize,
+}
+
+impl fmt::Display for NotEnoughDataError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "not enough data at offset {}: needed {}, but only {} left",
+            self.offset, self.needed, self.available
+        )
+    }
+}
+
+impl Error for NotEnoughDataError {}
+
+/// Errors that may happen when moving a [`SlicePos`](SlicePos) around.
 #[derive(Debug, Snafu, PartialEq, Eq)]
-pub struct NotEnoughDataError;
+#[snafu(visibility = "pub(crate)")]
+pub enum SeekError {
+    /// The target offset is past the end of the input slice.
+    #[snafu(display("offset {} is out of bounds (input length {})", offset, len))]
+    OutOfBounds {
+        /// The requested absolute offset
+        offset: usize,
+        /// The length of the input slice
+        len: usize,
+    },
+    /// An alignment of zero was requested.
+    #[snafu(display("invalid alignment {}", alignment))]
+    InvalidAlignment {
+        /// The requested alignment
+        alignment: usize,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ParseDriver;
+
+    use super::*;
+
+    #[test]
+    fn tag_no_case_ignores_ascii_case() {
+        let pd = &mut ParseDriver::new();
+        let parser = tag_no_case(b"select");
+
+        let (pos, matched) = parser(pd, BytePos::new(b"SeLeCt *")).unwrap();
+        assert_eq!(pos.offset, 6);
+        assert_eq!(matched, b"SeLeCt");
+
+        let (pos, matched) = parser(pd, BytePos::new(b"SELECT")).unwrap();
+        assert_eq!((pos.offset, matched), (6, &b"SELECT"[..]));
+
+        let (_, err) = parser(pd, BytePos::new(b"SELEC")).unwrap_err();
+        assert_eq!(
+            err,
+            TagError::NotEnoughData {
+                offset: 0,
+                expected: b"select"[..].into(),
+                available: 5,
+            }
+        );
+
+        let (_, err) = parser(pd, BytePos::new(b"INSERT")).unwrap_err();
+        assert_eq!(
+            err,
+            TagError::TagMismatch {
+                offset: 0,
+                expected: b"select"[..].into(),
+                found: b"INSERT".to_vec(),
+            }
+        );
+    }
+
+    #[test]
+    fn empty_tags_always_match() {
+        let pd = &mut ParseDriver::new();
+
+        let (pos, matched) = tag(b"")(pd, BytePos::new(b"abc")).unwrap();
+        assert_eq!(pos.offset, 0);
+        assert!(matched.is_empty());
+    }
+
+    #[test]
+    fn errors_describe_the_mismatch() {
+        let pd = &mut ParseDriver::new();
+        let pos = BytePos::new(b"GET /\n").advance_by(4);
+
+        let (_, err) = tag(b"/index")(pd, pos).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected `/index` at offset 4, but only 2 elements are left"
+        );
+
+        let (_, err) = tag(b"/\t")(pd, pos).unwrap_err();
+        assert_eq!(err.to_string(), "expected `/\\t` at offset 4, found `/\\n`");
+
+        let (_, err) = tag(&[1u16, 2])(pd, SlicePos::new(&[1, 3])).unwrap_err();
+        assert_eq!(err.to_string(), "expected [1, 2] at offset 0, found [1, 3]");
+
+        let (_, err) = pos.take(3).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "not enough data at offset 4: needed 3, but only 2 left"
+        );
+    }
+}
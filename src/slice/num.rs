@@ -1,5 +1,8 @@
 //! Parsers for different number types.
 
+use std::borrow::Cow;
+
+use crate::slice::{BytePos, NotEnoughDataError};
 use crate::Progress;
 
 macro_rules! impl_number {
@@ -36,6 +39,90 @@ macro_rules! impl_number {
                         $num::from_be_bytes(::std::convert::TryInto::try_into(n).unwrap())
                     })
             }
+
+            #[doc = "Parses `count` `" $num "`s in little-endian encoding into a `Vec`."]
+            ///
+            /// The input is bounds-checked once for all elements.
+            #[inline]
+            pub fn [<$num _le_array>]<'a, S>(
+                count: usize,
+            ) -> impl Fn(
+                &mut $crate::ParseDriver<S>,
+                $crate::slice::BytePos<'a>,
+            ) -> Progress<$crate::slice::BytePos<'a>, Vec<$num>, $crate::slice::NotEnoughDataError> {
+                move |_, pos| {
+                    take_array::<$num>(pos, count).map(|bytes| {
+                        bytes
+                            .chunks_exact(::std::mem::size_of::<$num>())
+                            .map(|n| $num::from_le_bytes(::std::convert::TryInto::try_into(n).unwrap()))
+                            .collect()
+                    })
+                }
+            }
+
+            #[doc = "Parses `count` `" $num "`s in big-endian encoding into a `Vec`."]
+            ///
+            /// The input is bounds-checked once for all elements.
+            #[inline]
+            pub fn [<$num _be_array>]<'a, S>(
+                count: usize,
+            ) -> impl Fn(
+                &mut $crate::ParseDriver<S>,
+                $crate::slice::BytePos<'a>,
+            ) -> Progress<$crate::slice::BytePos<'a>, Vec<$num>, $crate::slice::NotEnoughDataError> {
+                move |_, pos| {
+                    take_array::<$num>(pos, count).map(|bytes| {
+                        bytes
+                            .chunks_exact(::std::mem::size_of::<$num>())
+                            .map(|n| $num::from_be_bytes(::std::convert::TryInto::try_into(n).unwrap()))
+                            .collect()
+                    })
+                }
+            }
+
+            #[doc = "Parses `count` `" $num "`s in little-endian encoding."]
+            ///
+            /// Borrows the elements directly from the input if it is suitably aligned
+            /// and the target is little-endian, otherwise converts them into a `Vec`.
+            #[inline]
+            pub fn [<$num _le_slice>]<'a, S>(
+                count: usize,
+            ) -> impl Fn(
+                &mut $crate::ParseDriver<S>,
+                $crate::slice::BytePos<'a>,
+            ) -> Progress<$crate::slice::BytePos<'a>, Cow<'a, [$num]>, $crate::slice::NotEnoughDataError> {
+                move |pd, pos| {
+                    let (new_pos, bytes) = pahs!(take_array::<$num>(pos, count));
+                    match $crate::slice::cast_slice(bytes, count) {
+                        Some(nums) if cfg!(target_endian = "little") => {
+                            new_pos.success(Cow::Borrowed(nums))
+                        }
+                        _ => [<$num _le_array>](count)(pd, pos).map(Cow::Owned),
+                    }
+                }
+            }
+
+            #[doc = "Parses `count` `" $num "`s in big-endian encoding."]
+            ///
+            /// Borrows the elements directly from the input if it is suitably aligned
+            /// and the target is big-endian, otherwise converts them into a `Vec`.
+            #[inline]
+            pub fn [<$num _be_slice>]<'a, S>(
+                count: usize,
+            ) -> impl Fn(
+                &mut $crate::ParseDriver<S>,
+                $crate::slice::BytePos<'a>,
+            ) -> Progress<$crate::slice::BytePos<'a>, Cow<'a, [$num]>, $crate::slice::NotEnoughDataError> {
+                move |pd, pos| {
+                    let (new_pos, bytes) = pahs!(take_array::<$num>(pos, count));
+                    match $crate::slice::cast_slice(bytes, count) {
+                        Some(nums) if cfg!(target_endian = "big") => {
+                            new_pos.success(Cow::Borrowed(nums))
+                        }
+                        _ => [<$num _be_array>](count)(pd, pos).map(Cow::Owned),
+                    }
+                }
+            }
         }
     };
 
@@ -51,6 +138,22 @@ impl_number!(
     f32 f64
 );
 
+/// Takes the bytes of `count` elements of type `T` at once.
+///
+/// Succeeds without advancing if `count` is zero.
+#[inline]
+fn take_array<T>(
+    pos: BytePos<'_>,
+    count: usize,
+) -> Progress<BytePos<'_>, &[u8], NotEnoughDataError> {
+    match count.checked_mul(std::mem::size_of::<T>()) {
+        Some(0) => pos.success(&[]),
+        Some(len) => pos.take(len),
+        // more than can possibly be available
+        None => pos.failure(pos.not_enough_data(usize::MAX)),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::slice::{BytePos, NotEnoughDataError};
@@ -58,19 +161,30 @@ mod test {
 
     use super::*;
 
+    #[repr(C, align(8))]
+    struct Aligned([u8; 8]);
+
     #[test]
     fn fails_with_too_short_input() {
         let pd = &mut ParseDriver::new();
 
-        let p = BytePos { offset: 0, s: &[] };
+        let p = BytePos::new(&[]);
 
         let expected_u64 = Progress {
             pos: p,
-            status: Err(NotEnoughDataError),
+            status: Err(NotEnoughDataError {
+                offset: 0,
+                needed: 8,
+                available: 0,
+            }),
         };
         let expected_i8 = Progress {
             pos: p,
-            status: Err(NotEnoughDataError),
+            status: Err(NotEnoughDataError {
+                offset: 0,
+                needed: 1,
+                available: 0,
+            }),
         };
 
         assert_eq!(u64_le(pd, p), expected_u64);
@@ -83,25 +197,20 @@ mod test {
     fn parses_ints_correctly() {
         let pd = &mut ParseDriver::new();
 
-        let p = BytePos {
-            offset: 0,
-            s: &[0x01, 0x02, 0x03, 0x04, 0xD0, 0x0D, 0xF0, 0x0D],
-        };
+        let input = &[0x01, 0x02, 0x03, 0x04, 0xD0, 0x0D, 0xF0, 0x0D];
+        let p = BytePos::new(input);
 
         assert_eq!(
             u64_le(pd, p),
             Progress {
-                pos: BytePos { offset: 8, s: &[] },
+                pos: p.advance_by(8),
                 status: Ok(0x0D_F0_0D_D0_04_03_02_01_u64),
             }
         );
         assert_eq!(
             i16_le(pd, p),
             Progress {
-                pos: BytePos {
-                    offset: 2,
-                    s: &[0x03, 0x04, 0xD0, 0x0D, 0xF0, 0x0D]
-                },
+                pos: p.advance_by(2),
                 status: Ok(0x02_01_i16),
             }
         );
@@ -109,19 +218,88 @@ mod test {
         assert_eq!(
             u64_be(pd, p),
             Progress {
-                pos: BytePos { offset: 8, s: &[] },
+                pos: p.advance_by(8),
                 status: Ok(0x01_02_03_04_D0_0D_F0_0D_u64),
             }
         );
         assert_eq!(
             i16_be(pd, p),
             Progress {
-                pos: BytePos {
-                    offset: 2,
-                    s: &[0x03, 0x04, 0xD0, 0x0D, 0xF0, 0x0D]
-                },
+                pos: p.advance_by(2),
                 status: Ok(0x01_02_i16),
             }
         );
     }
+
+    #[test]
+    fn parses_arrays_correctly() {
+        let pd = &mut ParseDriver::new();
+
+        let input = &[0x01, 0x02, 0x03, 0x04, 0xD0, 0x0D, 0xF0, 0x0D];
+        let p = BytePos::new(input);
+
+        let (new_pos, vals) = u16_le_array(3)(pd, p).unwrap();
+        assert_eq!(new_pos.offset, 6);
+        assert_eq!(vals, &[0x02_01, 0x04_03, 0x0D_D0]);
+
+        let (new_pos, vals) = u32_be_array(2)(pd, p).unwrap();
+        assert_eq!(new_pos.offset, 8);
+        assert_eq!(vals, &[0x01_02_03_04, 0xD0_0D_F0_0D]);
+
+        let (new_pos, vals) = u64_le_array(0)(pd, p).unwrap();
+        assert_eq!(new_pos.offset, 0);
+        assert!(vals.is_empty());
+    }
+
+    #[test]
+    fn arrays_fail_with_too_short_input() {
+        let pd = &mut ParseDriver::new();
+
+        let input = &[0x01, 0x02, 0x03, 0x04, 0xD0, 0x0D, 0xF0, 0x0D];
+        let p = BytePos::new(input).advance_by(1);
+
+        let missing = |needed| NotEnoughDataError {
+            offset: 1,
+            needed,
+            available: 7,
+        };
+
+        assert_eq!(u16_le_array(4)(pd, p).unwrap_err(), (p, missing(8)));
+        assert_eq!(u16_be_slice(4)(pd, p).unwrap_err(), (p, missing(8)));
+        assert_eq!(
+            u64_le_array(usize::MAX)(pd, p).unwrap_err(),
+            (p, missing(usize::MAX))
+        );
+    }
+
+    #[test]
+    fn slices_borrow_aligned_native_data() {
+        let pd = &mut ParseDriver::new();
+
+        let input = Aligned([0x01, 0x02, 0x03, 0x04, 0xD0, 0x0D, 0xF0, 0x0D]);
+        let p = BytePos::new(&input.0);
+
+        let (native, foreign) = if cfg!(target_endian = "little") {
+            (u32_le_slice(2)(pd, p), u32_be_slice(2)(pd, p))
+        } else {
+            (u32_be_slice(2)(pd, p), u32_le_slice(2)(pd, p))
+        };
+
+        let (new_pos, native) = native.unwrap();
+        assert_eq!(new_pos.offset, 8);
+        assert!(matches!(native, Cow::Borrowed(..)));
+        assert_eq!(native[0].to_ne_bytes(), [0x01, 0x02, 0x03, 0x04]);
+
+        let (_, foreign) = foreign.unwrap();
+        assert!(matches!(foreign, Cow::Owned(..)));
+        assert_eq!(
+            foreign[0].swap_bytes().to_ne_bytes(),
+            [0x01, 0x02, 0x03, 0x04]
+        );
+
+        // misaligned data gets copied
+        let (_, vals) = u16_le_slice(2)(pd, p.advance_by(1)).unwrap();
+        assert!(matches!(vals, Cow::Owned(..)));
+        assert_eq!(&*vals, &[0x03_02, 0xD0_04]);
+    }
 }
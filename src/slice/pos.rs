@@ -1,6 +1,6 @@
 use crate::{Pos, Progress};
 
-use super::NotEnoughDataError;
+use super::{InvalidAlignment, NotEnoughDataError, OutOfBounds, SeekError};
 
 /// Convenience alias for byte slices.
 pub type BytePos<'a> = SlicePos<'a, u8>;
@@ -12,12 +12,21 @@ pub type BytePos<'a> = SlicePos<'a, u8>;
 /// The offset is tracked separately from the slice to always know how far along the parsing is.
 /// It is especially useful for error handling, as you can save the the offset
 /// instead of the slice reference, allowing your error types to be `'static`.
+///
+/// The original input slice is kept around as well, allowing to seek to absolute offsets
+/// (see [`seek_to`](SlicePos::seek_to)). As it is private, slice positions can't be created
+/// via struct literals, use [`new`](SlicePos::new) instead.
+///
+/// Parsers advance via `offset` and `s`, which are authoritative for the current position.
+/// Seeking only relies on the origin and `offset`, so when modifying the fields directly,
+/// keep `s` equal to `&origin()[offset..]`.
 #[derive(Debug)]
 pub struct SlicePos<'a, T> {
     /// The offset to the beginning of the parsing process
     pub offset: usize,
     /// The current input slice
     pub s: &'a [T],
+    origin: &'a [T],
 }
 
 impl<'a, T> SlicePos<'a, T> {
@@ -27,16 +36,109 @@ impl<'a, T> SlicePos<'a, T> {
         Self {
             offset: 0,
             s: slice,
+            origin: slice,
         }
     }
 
     /// Advances the slice position by `offset` elements. Panics if the new position would
-    /// be out of bounds.
+    /// be out of bounds, unless the `panic_free` feature is enabled, which clamps it to the
+    /// end of the input slice instead.
+    ///
+    /// See [`skip`](SlicePos::skip) for a bounds-checked alternative, which parsers should
+    /// prefer.
     #[inline]
     pub fn advance_by(self, offset: usize) -> Self {
+        let offset = if cfg!(feature = "panic_free") {
+            offset.min(self.s.len())
+        } else {
+            offset
+        };
+
         Self {
             s: &self.s[offset..],
             offset: self.offset + offset,
+            origin: self.origin,
+        }
+    }
+
+    /// Returns the complete input slice the parsing process started with.
+    #[inline]
+    pub fn origin(&self) -> &'a [T] {
+        self.origin
+    }
+
+    /// Returns the number of elements left in the input slice.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.s.len()
+    }
+
+    /// Skips `count` elements, advancing the slice position by that many elements.
+    ///
+    /// Fails if more elements are to be skipped than there are left in the input slice.
+    #[inline]
+    pub fn skip(self, count: usize) -> Progress<SlicePos<'a, T>, (), NotEnoughDataError> {
+        if count > self.s.len() {
+            self.failure(self.not_enough_data(count))
+        } else {
+            self.advance_by(count).success(())
+        }
+    }
+
+    /// Moves the slice position to the absolute `offset` into the
+    /// [`origin`](SlicePos::origin) slice, either forwards or backwards.
+    ///
+    /// Fails if `offset` is past the end of the origin slice.
+    #[inline]
+    pub fn seek_to(self, offset: usize) -> Progress<SlicePos<'a, T>, (), SeekError> {
+        match self.origin.get(offset..) {
+            Some(s) => SlicePos {
+                offset,
+                s,
+                origin: self.origin,
+            }
+            .success(()),
+            None => self.failure(
+                OutOfBounds {
+                    offset,
+                    len: self.origin.len(),
+                }
+                .build(),
+            ),
+        }
+    }
+
+    /// Skips elements until the absolute offset is a multiple of `alignment`.
+    ///
+    /// Fails if `alignment` is zero, or if the aligned position would be past the end
+    /// of the input slice.
+    #[inline]
+    pub fn align_to(self, alignment: usize) -> Progress<SlicePos<'a, T>, (), SeekError> {
+        if alignment == 0 {
+            return self.failure(InvalidAlignment { alignment }.build());
+        }
+
+        let padding = (alignment - self.offset % alignment) % alignment;
+        if padding > self.s.len() {
+            self.failure(
+                OutOfBounds {
+                    offset: self.offset + padding,
+                    len: self.origin.len(),
+                }
+                .build(),
+            )
+        } else {
+            self.advance_by(padding).success(())
+        }
+    }
+
+    /// Creates an error for `needed` elements missing at this position.
+    #[inline]
+    pub(crate) fn not_enough_data(&self, needed: usize) -> NotEnoughDataError {
+        NotEnoughDataError {
+            offset: self.offset,
+            needed,
+            available: self.s.len(),
         }
     }
 
@@ -58,15 +160,29 @@ impl<'a, T> SlicePos<'a, T> {
     ///
     /// Fails if more elements are requested than there are left in the input slice.
     ///
-    /// Panics if zero elements are requested, in order to prevent infinite loops.
+    /// Panics if zero elements are requested, in order to prevent infinite loops. With the
+    /// `panic_free` feature enabled, it succeeds with an empty slice instead, like
+    /// [`try_take`](SlicePos::try_take), which never panics.
     #[inline]
     pub fn take(self, count: usize) -> Progress<SlicePos<'a, T>, &'a [T], NotEnoughDataError> {
-        if count == 0 {
+        if count == 0 && !cfg!(feature = "panic_free") {
             panic!("take called with count == 0");
         }
 
+        self.try_take(count)
+    }
+
+    /// Takes `len` elements from the slice, advancing the slice position by that many elements.
+    ///
+    /// Fails if more elements are requested than there are left in the input slice.
+    ///
+    /// Succeeds with an empty slice if zero elements are requested. Repetitions of parsers that
+    /// don't progress are caught by the combinators, see
+    /// [`DriverError::NoProgress`](crate::DriverError::NoProgress).
+    #[inline]
+    pub fn try_take(self, count: usize) -> Progress<SlicePos<'a, T>, &'a [T], NotEnoughDataError> {
         if count > self.s.len() {
-            self.failure(NotEnoughDataError)
+            self.failure(self.not_enough_data(count))
         } else {
             let matched = &self.s[0..count];
             self.advance_by(count).success(matched)
@@ -79,7 +195,7 @@ impl<'a, T> SlicePos<'a, T> {
     #[inline]
     pub fn take1(self) -> Progress<SlicePos<'a, T>, &'a T, NotEnoughDataError> {
         if self.s.is_empty() {
-            self.failure(NotEnoughDataError)
+            self.failure(self.not_enough_data(1))
         } else {
             let matched = &self.s[0];
             self.advance_by(1).success(matched)
@@ -90,7 +206,16 @@ impl<'a, T> SlicePos<'a, T> {
 impl<'a, T> Pos for SlicePos<'a, T> {
     #[inline]
     fn zero() -> Self {
-        SlicePos { offset: 0, s: &[] }
+        SlicePos {
+            offset: 0,
+            s: &[],
+            origin: &[],
+        }
+    }
+
+    #[inline]
+    fn offset(&self) -> usize {
+        self.offset
     }
 }
 
@@ -124,3 +249,121 @@ impl<'a, T> PartialEq for SlicePos<'a, T> {
 }
 
 impl<'a, T> Eq for SlicePos<'a, T> {}
+
+#[cfg(test)]
+mod test {
+    use crate::slice::{BytePos, NotEnoughDataError, SeekError};
+
+    #[test]
+    fn skip_is_bounds_checked() {
+        let input = &[0u8, 1, 2, 3];
+        let pos = BytePos::new(input);
+
+        let (new_pos, _) = pos.skip(4).unwrap();
+        assert_eq!(new_pos.offset, 4);
+        assert_eq!(new_pos.remaining(), 0);
+
+        let (new_pos, err) = pos.advance_by(1).skip(4).unwrap_err();
+        assert_eq!(new_pos.offset, 1);
+        assert_eq!(
+            err,
+            NotEnoughDataError {
+                offset: 1,
+                needed: 4,
+                available: 3
+            }
+        );
+    }
+
+    #[test]
+    fn seek_to_moves_to_absolute_offsets() {
+        let input = &[0u8, 1, 2, 3, 4, 5];
+        let pos = BytePos::new(input).advance_by(4);
+
+        let (new_pos, _) = pos.seek_to(1).unwrap();
+        assert_eq!(new_pos.offset, 1);
+        assert_eq!(new_pos.s, &input[1..]);
+
+        let (new_pos, _) = new_pos.seek_to(6).unwrap();
+        assert_eq!(new_pos.offset, 6);
+        assert_eq!(new_pos.remaining(), 0);
+
+        let (new_pos, err) = pos.seek_to(7).unwrap_err();
+        assert_eq!(new_pos.offset, 4);
+        assert_eq!(err, SeekError::OutOfBounds { offset: 7, len: 6 });
+    }
+
+    #[test]
+    fn align_to_skips_padding() {
+        let input = &[0u8; 10];
+        let pos = BytePos::new(input);
+
+        let (new_pos, _) = pos.align_to(4).unwrap();
+        assert_eq!(new_pos.offset, 0);
+
+        let (new_pos, _) = pos.advance_by(1).align_to(4).unwrap();
+        assert_eq!(new_pos.offset, 4);
+
+        let (new_pos, _) = pos.advance_by(5).align_to(3).unwrap();
+        assert_eq!(new_pos.offset, 6);
+
+        let (_, err) = pos.advance_by(9).align_to(8).unwrap_err();
+        assert_eq!(
+            err,
+            SeekError::OutOfBounds {
+                offset: 16,
+                len: 10
+            }
+        );
+
+        let (_, err) = pos.align_to(0).unwrap_err();
+        assert_eq!(err, SeekError::InvalidAlignment { alignment: 0 });
+    }
+
+    #[test]
+    fn try_take_allows_empty_takes() {
+        let input = &[0u8, 1, 2, 3];
+        let pos = BytePos::new(input);
+
+        let (new_pos, taken) = pos.try_take(0).unwrap();
+        assert_eq!(new_pos.offset, 0);
+        assert!(taken.is_empty());
+
+        let (new_pos, taken) = pos.try_take(3).unwrap();
+        assert_eq!(new_pos.offset, 3);
+        assert_eq!(taken, &[0, 1, 2]);
+
+        let (new_pos, err) = pos.try_take(5).unwrap_err();
+        assert_eq!(new_pos.offset, 0);
+        assert_eq!(
+            err,
+            NotEnoughDataError {
+                offset: 0,
+                needed: 5,
+                available: 4
+            }
+        );
+    }
+
+    #[cfg(not(feature = "panic_free"))]
+    #[test]
+    #[should_panic(expected = "take called with count == 0")]
+    fn take_panics_on_empty_takes() {
+        let _ = BytePos::new(&[0u8]).take(0);
+    }
+
+    #[cfg(feature = "panic_free")]
+    #[test]
+    fn panic_free_clamps_out_of_bounds_positions() {
+        let input = &[0u8, 1, 2, 3];
+        let pos = BytePos::new(input);
+
+        let new_pos = pos.advance_by(10);
+        assert_eq!(new_pos.offset, 4);
+        assert_eq!(new_pos.remaining(), 0);
+
+        let (new_pos, taken) = pos.take(0).unwrap();
+        assert_eq!(new_pos.offset, 0);
+        assert!(taken.is_empty());
+    }
+}
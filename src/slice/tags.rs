@@ -0,0 +1,194 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::error_accumulator::{Expectation, Expected};
+use crate::{ParseDriver, Progress};
+
+use super::{SlicePos, TagElement};
+
+/// Matches the input slice against all of the `tags`, succeeding with the index
+/// of the longest tag that matched.
+///
+/// The tags are compiled into a trie up front, so the input is only examined once,
+/// regardless of how many tags there are. If a tag is contained multiple times, the
+/// index of its first occurrence is returned.
+#[inline]
+pub fn one_of_tags<'a, T, S>(
+    tags: &[&[T]],
+) -> impl Fn(&mut ParseDriver<S>, SlicePos<'a, T>) -> Progress<SlicePos<'a, T>, usize, OneOfTagsError<T>>
+where
+    T: Ord + Clone + 'a,
+{
+    let trie = Trie::new(tags);
+    let candidates: Arc<[Vec<T>]> = tags.iter().map(|tag| tag.to_vec()).collect();
+
+    move |_, pos| match trie.longest_match(pos.s) {
+        Some((index, len)) => pos.advance_by(len).success(index),
+        None => pos.failure(OneOfTagsError::NoTagMatched {
+            offset: pos.offset,
+            candidates: Arc::clone(&candidates),
+        }),
+    }
+}
+
+/// Errors that may happen when using [`one_of_tags`](one_of_tags).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OneOfTagsError<T> {
+    /// None of the tags matched.
+    NoTagMatched {
+        /// The offset at which one of the tags was expected
+        offset: usize,
+        /// All tags that were tried
+        candidates: Arc<[Vec<T>]>,
+    },
+}
+
+impl<T: TagElement> fmt::Display for OneOfTagsError<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OneOfTagsError::NoTagMatched { offset, candidates } => {
+                f.write_str("expected one of ")?;
+                for (i, candidate) in candidates.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    T::fmt_tag(candidate, f)?;
+                }
+                write!(f, " at offset {}", offset)
+            }
+        }
+    }
+}
+
+impl<T: TagElement> Error for OneOfTagsError<T> {}
+
+impl Expectation for OneOfTagsError<u8> {
+    #[inline]
+    fn expected(&self) -> Vec<Expected> {
+        match self {
+            OneOfTagsError::NoTagMatched { candidates, .. } => candidates
+                .iter()
+                .map(|tag| Expected::Tag(tag.clone()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Node<T> {
+    /// Child nodes, sorted by their element
+    children: Vec<(T, usize)>,
+    /// The index of the tag ending at this node, if any
+    tag: Option<usize>,
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Self {
+            children: Vec::new(),
+            tag: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Trie<T> {
+    nodes: Vec<Node<T>>,
+}
+
+impl<T: Ord + Clone> Trie<T> {
+    fn new(tags: &[&[T]]) -> Self {
+        let mut nodes: Vec<Node<T>> = vec![Node::default()];
+
+        for (index, tag) in tags.iter().enumerate() {
+            let mut current = 0;
+            for elem in tag.iter() {
+                current = match nodes[current]
+                    .children
+                    .binary_search_by(|(e, _)| e.cmp(elem))
+                {
+                    Ok(child) => nodes[current].children[child].1,
+                    Err(insert_at) => {
+                        let new_node = nodes.len();
+                        nodes.push(Node::default());
+                        nodes[current]
+                            .children
+                            .insert(insert_at, (elem.clone(), new_node));
+                        new_node
+                    }
+                };
+            }
+
+            nodes[current].tag.get_or_insert(index);
+        }
+
+        Self { nodes }
+    }
+
+    /// Returns the index and length of the longest tag that `input` starts with.
+    fn longest_match(&self, input: &[T]) -> Option<(usize, usize)> {
+        let mut current = 0;
+        let mut longest = self.nodes[0].tag.map(|index| (index, 0));
+
+        for (len, elem) in input.iter().enumerate() {
+            let node = &self.nodes[current];
+            match node.children.binary_search_by(|(e, _)| e.cmp(elem)) {
+                Ok(child) => current = node.children[child].1,
+                Err(..) => break,
+            }
+
+            if let Some(index) = self.nodes[current].tag {
+                longest = Some((index, len + 1));
+            }
+        }
+
+        longest
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::slice::BytePos;
+    use crate::ParseDriver;
+
+    use super::*;
+
+    #[test]
+    fn it_matches_the_longest_tag() {
+        let pd = &mut ParseDriver::new();
+        let parser = one_of_tags(&[&b"GET"[..], b"PUT", b"POST", b"PO"]);
+
+        let (pos, index) = parser(pd, BytePos::new(b"POST /")).unwrap();
+        assert_eq!(pos.offset, 4);
+        assert_eq!(index, 2);
+
+        let (pos, index) = parser(pd, BytePos::new(b"POS")).unwrap();
+        assert_eq!(pos.offset, 2);
+        assert_eq!(index, 3);
+
+        let (pos, index) = parser(pd, BytePos::new(b"GET")).unwrap();
+        assert_eq!(pos.offset, 3);
+        assert_eq!(index, 0);
+    }
+
+    #[test]
+    fn it_lists_candidates_on_failure() {
+        let pd = &mut ParseDriver::new();
+        let parser = one_of_tags(&[&b"GET"[..], b"PUT"]);
+
+        let (pos, err) = parser(pd, BytePos::new(b"_GE").advance_by(1)).unwrap_err();
+        assert_eq!(pos.offset, 1);
+        let OneOfTagsError::NoTagMatched { offset, candidates } = err;
+        assert_eq!(offset, 1);
+        assert_eq!(&*candidates, &[b"GET".to_vec(), b"PUT".to_vec()]);
+
+        let (_, err) = parser(pd, BytePos::new(b"_GE").advance_by(1)).unwrap_err();
+        assert_eq!(err.to_string(), "expected one of `GET`, `PUT` at offset 1");
+
+        let parser = one_of_tags(&[&[1u16, 2][..], &[3]]);
+        let (_, err) = parser(pd, SlicePos::new(&[4])).unwrap_err();
+        assert_eq!(err.to_string(), "expected one of [1, 2], [3] at offset 0");
+    }
+}
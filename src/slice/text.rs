@@ -0,0 +1,370 @@
+//! Parsers for numbers in ASCII text.
+
+use snafu::Snafu;
+
+use crate::error_accumulator::{Expectation, Expected};
+use crate::slice::BytePos;
+use crate::{DriverError, ErrorKind, ParseDriver, Progress, Recoverable};
+
+/// Integer types that can be parsed from text.
+pub trait Integer: Copy {
+    /// `true` if the type can represent negative numbers.
+    const SIGNED: bool;
+
+    /// Converts the ASCII `digits` in the specified `radix` into a number.
+    ///
+    /// Returns `None` if the number doesn't fit into the type. `digits` must only contain
+    /// valid digits for the `radix`.
+    fn from_digits(digits: &[u8], radix: u32, negative: bool) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($signed:expr => $($ty:ident)*) => {
+        $(
+            impl Integer for $ty {
+                const SIGNED: bool = $signed;
+
+                #[inline]
+                fn from_digits(digits: &[u8], radix: u32, negative: bool) -> Option<Self> {
+                    let mut acc: $ty = 0;
+                    for &d in digits {
+                        // digits are validated by the caller, and radix is at most 36,
+                        // so both always fit into the type
+                        let d = char::from(d).to_digit(radix).unwrap() as $ty;
+                        acc = acc.checked_mul(radix as $ty)?;
+
+                        // accumulate negative numbers downwards, as `MIN` has
+                        // a larger magnitude than `MAX`
+                        acc = if negative {
+                            acc.checked_sub(d)?
+                        } else {
+                            acc.checked_add(d)?
+                        };
+                    }
+                    Some(acc)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(false => u8 u16 u32 u64 u128 usize);
+impl_integer!(true => i8 i16 i32 i64 i128 isize);
+
+/// Floating point types that can be parsed from text.
+pub trait Float: std::str::FromStr {}
+
+impl Float for f32 {}
+impl Float for f64 {}
+
+/// Parses a decimal integer with an optional sign.
+///
+/// A `-` sign is only accepted for signed types.
+#[inline]
+pub fn int<'a, T, S>(
+    _pd: &mut ParseDriver<S>,
+    pos: BytePos<'a>,
+) -> Progress<BytePos<'a>, T, NumberError>
+where
+    T: Integer,
+{
+    parse_int(pos, 10, false)
+}
+
+/// Parses an integer in the specified `radix` (`2..=36`) with an optional sign.
+///
+/// A `-` sign is only accepted for signed types. An invalid `radix` results in an
+/// irrecoverable failure.
+#[inline]
+pub fn int_radix<'a, T, S>(
+    radix: u32,
+) -> impl Fn(&mut ParseDriver<S>, BytePos<'a>) -> Progress<BytePos<'a>, T, NumberError>
+where
+    T: Integer,
+{
+    move |_, pos| parse_int(pos, radix, false)
+}
+
+/// Parses an integer with an optional sign, followed by an optional radix prefix
+/// (`0x` for hexadecimal, `0o` for octal, `0b` for binary). Numbers without a
+/// prefix are parsed as decimal.
+///
+/// A `-` sign is only accepted for signed types.
+#[inline]
+pub fn int_prefixed<'a, T, S>(
+    _pd: &mut ParseDriver<S>,
+    pos: BytePos<'a>,
+) -> Progress<BytePos<'a>, T, NumberError>
+where
+    T: Integer,
+{
+    parse_int(pos, 10, true)
+}
+
+/// Parses a floating point number with an optional sign, fractional part and exponent,
+/// like `-12.5e-3`.
+///
+/// The result is correctly rounded.
+#[inline]
+pub fn float<'a, T, S>(
+    _pd: &mut ParseDriver<S>,
+    pos: BytePos<'a>,
+) -> Progress<BytePos<'a>, T, NumberError>
+where
+    T: Float,
+{
+    let s = pos.s;
+    let mut len = sign_len(s);
+
+    let int_digits = count_digits(&s[len..], 10);
+    len += int_digits;
+
+    let mut frac_digits = 0;
+    if s.get(len) == Some(&b'.') {
+        frac_digits = count_digits(&s[len + 1..], 10);
+        if frac_digits > 0 || int_digits > 0 {
+            len += 1 + frac_digits;
+        }
+    }
+
+    if int_digits == 0 && frac_digits == 0 {
+        return pos.failure(NoDigits { offset: pos.offset }.build());
+    }
+
+    if let Some(b'e') | Some(b'E') = s.get(len) {
+        let exp_sign = sign_len(&s[len + 1..]);
+        let exp_digits = count_digits(&s[len + 1 + exp_sign..], 10);
+
+        // a dangling `e` isn't part of the number
+        if exp_digits > 0 {
+            len += 1 + exp_sign + exp_digits;
+        }
+    }
+
+    // only ASCII characters have been consumed, so this can't fail
+    let text = std::str::from_utf8(&s[..len]).unwrap();
+    match text.parse() {
+        Ok(val) => pos.advance_by(len).success(val),
+        Err(..) => pos.failure(NoDigits { offset: pos.offset }.build()),
+    }
+}
+
+fn parse_int<T: Integer>(
+    pos: BytePos<'_>,
+    mut radix: u32,
+    detect_prefix: bool,
+) -> Progress<BytePos<'_>, T, NumberError> {
+    if !(2..=36).contains(&radix) {
+        return pos.failure(
+            InvalidRadix {
+                offset: pos.offset,
+                radix,
+            }
+            .build(),
+        );
+    }
+
+    let s = pos.s;
+    let negative = s.first() == Some(&b'-');
+    if negative && !T::SIGNED {
+        return pos.failure(NoDigits { offset: pos.offset }.build());
+    }
+
+    let mut len = sign_len(s);
+
+    if detect_prefix && s.get(len) == Some(&b'0') {
+        let prefixed_radix = match s.get(len + 1) {
+            Some(b'x') | Some(b'X') => Some(16),
+            Some(b'o') | Some(b'O') => Some(8),
+            Some(b'b') | Some(b'B') => Some(2),
+            _ => None,
+        };
+
+        // only treat it as a prefix if digits follow, `0x` on its own is just a `0`
+        if let Some(prefixed_radix) = prefixed_radix {
+            if count_digits(&s[len + 2..], prefixed_radix) > 0 {
+                radix = prefixed_radix;
+                len += 2;
+            }
+        }
+    }
+
+    let digits = count_digits(&s[len..], radix);
+    if digits == 0 {
+        return pos.failure(NoDigits { offset: pos.offset }.build());
+    }
+
+    match T::from_digits(&s[len..len + digits], radix, negative) {
+        Some(val) => pos.advance_by(len + digits).success(val),
+        None => pos.failure(Overflow { offset: pos.offset }.build()),
+    }
+}
+
+fn sign_len(s: &[u8]) -> usize {
+    match s.first() {
+        Some(b'+') | Some(b'-') => 1,
+        _ => 0,
+    }
+}
+
+fn count_digits(s: &[u8], radix: u32) -> usize {
+    s.iter()
+        .take_while(|&&b| char::from(b).is_digit(radix))
+        .count()
+}
+
+/// Errors that may happen when parsing numbers from text.
+#[derive(Debug, Snafu, PartialEq, Eq)]
+#[snafu(visibility = "pub(crate)")]
+pub enum NumberError {
+    /// The input didn't start with a number.
+    #[snafu(display("expected a number at offset {}", offset))]
+    NoDigits {
+        /// The offset at which the number was expected
+        offset: usize,
+    },
+    /// The number didn't fit into the target type.
+    #[snafu(display("number at offset {} is out of range", offset))]
+    Overflow {
+        /// The offset of the number
+        offset: usize,
+    },
+    /// An invalid radix was specified.
+    #[snafu(display("invalid radix {} at offset {}, expected 2 to 36", radix, offset))]
+    InvalidRadix {
+        /// The offset at which the number was expected
+        offset: usize,
+        /// The specified radix
+        radix: u32,
+    },
+    /// The combinators failed, e.g. as the budget was exhausted.
+    #[snafu(display("{}", source))]
+    Driver {
+        /// The error of the combinators
+        source: DriverError,
+    },
+}
+
+impl Recoverable for NumberError {
+    /// A missing number is recoverable, as something else might be expected instead.
+    /// Numbers that are out of range are not, and an invalid radix is a bug in the grammar.
+    /// Driver errors are fatal.
+    #[inline]
+    fn kind(&self) -> ErrorKind {
+        match self {
+            NumberError::NoDigits { .. } => ErrorKind::Backtrack,
+            NumberError::Overflow { .. } => ErrorKind::Cut,
+            NumberError::InvalidRadix { .. } | NumberError::Driver { .. } => ErrorKind::Fatal,
+        }
+    }
+
+    #[inline]
+    fn as_expectation(&self) -> Option<&dyn Expectation> {
+        Some(self)
+    }
+
+    #[inline]
+    fn from_driver_error(source: DriverError) -> Self {
+        NumberError::Driver { source }
+    }
+}
+
+impl Expectation for NumberError {
+    #[inline]
+    fn expected(&self) -> Vec<Expected> {
+        match self {
+            NumberError::Driver { .. } => Vec::new(),
+            _ => vec![Expected::Rule("number".into())],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::slice::BytePos;
+    use crate::ParseDriver;
+
+    use super::*;
+
+    #[test]
+    fn parses_decimal_ints() {
+        let pd = &mut ParseDriver::new();
+
+        let (pos, n) = int::<i32, _>(pd, BytePos::new(b"-1234,")).unwrap();
+        assert_eq!((pos.offset, n), (5, -1234));
+
+        let (pos, n) = int::<u8, _>(pd, BytePos::new(b"+255")).unwrap();
+        assert_eq!((pos.offset, n), (4, 255));
+
+        let (pos, n) = int::<i8, _>(pd, BytePos::new(b"-128")).unwrap();
+        assert_eq!((pos.offset, n), (4, -128));
+
+        let (pos, err) = int::<u32, _>(pd, BytePos::new(b"-1")).unwrap_err();
+        assert_eq!((pos.offset, err), (0, NumberError::NoDigits { offset: 0 }));
+
+        let (pos, err) = int::<u32, _>(pd, BytePos::new(b"+")).unwrap_err();
+        assert_eq!((pos.offset, err), (0, NumberError::NoDigits { offset: 0 }));
+    }
+
+    #[test]
+    fn overflow_is_irrecoverable() {
+        let pd = &mut ParseDriver::new();
+
+        let (pos, err) = int::<u8, _>(pd, BytePos::new(b"256")).unwrap_err();
+        assert_eq!(pos.offset, 0);
+        assert_eq!(err, NumberError::Overflow { offset: 0 });
+        assert!(!err.recoverable());
+
+        let (_, err) = int::<i8, _>(pd, BytePos::new(b"-129")).unwrap_err();
+        assert_eq!(err, NumberError::Overflow { offset: 0 });
+    }
+
+    #[test]
+    fn parses_other_radixes() {
+        let pd = &mut ParseDriver::new();
+
+        let (pos, n) = int_radix::<u32, _>(16)(pd, BytePos::new(b"fF7g")).unwrap();
+        assert_eq!((pos.offset, n), (3, 0xFF7));
+
+        let (pos, n) = int_prefixed::<i64, _>(pd, BytePos::new(b"-0x1F")).unwrap();
+        assert_eq!((pos.offset, n), (5, -0x1F));
+
+        let (pos, n) = int_prefixed::<u8, _>(pd, BytePos::new(b"0o17")).unwrap();
+        assert_eq!((pos.offset, n), (4, 0o17));
+
+        let (pos, n) = int_prefixed::<u8, _>(pd, BytePos::new(b"0b1012")).unwrap();
+        assert_eq!((pos.offset, n), (5, 0b101));
+
+        let (pos, n) = int_prefixed::<u8, _>(pd, BytePos::new(b"0xg")).unwrap();
+        assert_eq!((pos.offset, n), (1, 0));
+
+        let (_, err) = int_radix::<u32, _>(37)(pd, BytePos::new(b"1")).unwrap_err();
+        assert_eq!(
+            err,
+            NumberError::InvalidRadix {
+                offset: 0,
+                radix: 37
+            }
+        );
+    }
+
+    #[test]
+    fn parses_floats() {
+        let pd = &mut ParseDriver::new();
+
+        let (pos, n) = float::<f64, _>(pd, BytePos::new(b"-12.5e-3]")).unwrap();
+        assert_eq!((pos.offset, n), (8, -12.5e-3));
+
+        let (pos, n) = float::<f64, _>(pd, BytePos::new(b".5")).unwrap();
+        assert_eq!((pos.offset, n), (2, 0.5));
+
+        let (pos, n) = float::<f32, _>(pd, BytePos::new(b"3.e")).unwrap();
+        assert_eq!((pos.offset, n), (2, 3.0));
+
+        let (pos, n) = float::<f64, _>(pd, BytePos::new(b"0.1")).unwrap();
+        assert_eq!((pos.offset, n), (3, 0.1));
+
+        let (pos, err) = float::<f64, _>(pd, BytePos::new(b"-.e5")).unwrap_err();
+        assert_eq!((pos.offset, err), (0, NumberError::NoDigits { offset: 0 }));
+    }
+}
@@ -0,0 +1,294 @@
+use std::mem::{align_of, size_of};
+use std::ptr::NonNull;
+
+use snafu::Snafu;
+
+use crate::{ParseDriver, Progress};
+
+use super::{BytePos, NotEnoughDataError};
+
+/// Marker trait for plain-old-data types that can be viewed directly from bytes.
+///
+/// # Safety
+///
+/// Implementors have to guarantee that every bit pattern is a valid value of the type,
+/// and that the type does not contain any padding bytes.
+///
+/// For structs, prefer deriving this trait (via the `derive` feature), which verifies
+/// these requirements at compile time.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($ty:ty)*) => {
+        $(unsafe impl Pod for $ty {})*
+    };
+}
+
+impl_pod!(
+    u8 u16 u32 u64 u128 usize
+    i8 i16 i32 i64 i128 isize
+    f32 f64
+);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// Views the next `size_of::<T>()` bytes as a `T`, without copying.
+///
+/// Fails if there is not enough data left, or if the data is not aligned
+/// correctly for `T`. See [`view_unaligned`](view_unaligned) if the data may be unaligned.
+#[inline]
+pub fn view<'a, T: Pod, S>(
+) -> impl Fn(&mut ParseDriver<S>, BytePos<'a>) -> Progress<BytePos<'a>, &'a T, ViewError> {
+    move |_, pos| {
+        if pos.s.len() < size_of::<T>() {
+            return pos.failure(ViewError::NotEnoughData {
+                source: pos.not_enough_data(size_of::<T>()),
+            });
+        }
+
+        if pos.s.as_ptr().align_offset(align_of::<T>()) != 0 {
+            return pos.failure(
+                Misaligned {
+                    offset: pos.offset,
+                    alignment: align_of::<T>(),
+                }
+                .build(),
+            );
+        }
+
+        // Safety: length and alignment have been checked above,
+        // and `T: Pod` is valid for any bit pattern
+        let val = unsafe { &*(pos.s.as_ptr() as *const T) };
+        pos.advance_by(size_of::<T>()).success(val)
+    }
+}
+
+/// Reads the next `size_of::<T>()` bytes as a `T`, copying it out of the input.
+///
+/// Fails if there is not enough data left.
+#[inline]
+pub fn view_unaligned<'a, T: Pod, S>(
+) -> impl Fn(&mut ParseDriver<S>, BytePos<'a>) -> Progress<BytePos<'a>, T, NotEnoughDataError> {
+    move |_, pos| {
+        if pos.s.len() < size_of::<T>() {
+            return pos.failure(pos.not_enough_data(size_of::<T>()));
+        }
+
+        // Safety: length has been checked above, `read_unaligned` doesn't need any alignment,
+        // and `T: Pod` is valid for any bit pattern
+        let val = unsafe { std::ptr::read_unaligned(pos.s.as_ptr() as *const T) };
+        pos.advance_by(size_of::<T>()).success(val)
+    }
+}
+
+/// Views the next `count * size_of::<T>()` bytes as a slice of `T`s, without copying.
+///
+/// Fails if there is not enough data left, or if the data is not aligned
+/// correctly for `T`.
+#[inline]
+pub fn view_slice<'a, T: Pod, S>(
+    count: usize,
+) -> impl Fn(&mut ParseDriver<S>, BytePos<'a>) -> Progress<BytePos<'a>, &'a [T], ViewError> {
+    move |_, pos| {
+        // overflowing lengths are more than can possibly be available
+        let len = count.saturating_mul(size_of::<T>());
+        if len > pos.s.len() {
+            return pos.failure(ViewError::NotEnoughData {
+                source: pos.not_enough_data(len),
+            });
+        }
+
+        match cast_slice(&pos.s[..len], count) {
+            Some(val) => pos.advance_by(len).success(val),
+            None => pos.failure(
+                Misaligned {
+                    offset: pos.offset,
+                    alignment: align_of::<T>(),
+                }
+                .build(),
+            ),
+        }
+    }
+}
+
+/// Reinterprets the first `count` `T`s in `bytes` as a slice, ignoring trailing bytes.
+///
+/// Returns `None` if `bytes` isn't aligned correctly for `T`. Zero-sized types don't occupy
+/// any bytes, so they are always aligned.
+#[inline]
+pub(crate) fn cast_slice<T: Pod>(bytes: &[u8], count: usize) -> Option<&[T]> {
+    if size_of::<T>() == 0 {
+        // Safety: a dangling pointer is valid for slices of zero-sized types,
+        // and `T: Pod` is valid for any (empty) bit pattern
+        return Some(unsafe { std::slice::from_raw_parts(NonNull::dangling().as_ptr(), count) });
+    }
+
+    if bytes.as_ptr().align_offset(align_of::<T>()) != 0 {
+        return None;
+    }
+
+    // Safety: the length stays within `bytes`, alignment has been checked above,
+    // and `T: Pod` is valid for any bit pattern
+    let count = count.min(bytes.len() / size_of::<T>());
+    Some(unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const T, count) })
+}
+
+/// Errors that may happen when using [`view`](view) or [`view_slice`](view_slice).
+#[derive(Debug, Snafu, PartialEq, Eq)]
+#[snafu(visibility = "pub(crate)")]
+pub enum ViewError {
+    /// The input slice was too short.
+    #[snafu(display("{}", source))]
+    NotEnoughData {
+        /// How much data was missing
+        source: NotEnoughDataError,
+    },
+    /// The input data wasn't aligned correctly for the viewed type.
+    #[snafu(display(
+        "input data at offset {} is not aligned to {} bytes",
+        offset,
+        alignment
+    ))]
+    Misaligned {
+        /// The offset of the misaligned data
+        offset: usize,
+        /// The alignment required by the viewed type
+        alignment: usize,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use crate::slice::{BytePos, ViewError};
+    use crate::ParseDriver;
+
+    use super::*;
+
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    #[repr(C)]
+    struct Header {
+        magic: [u8; 4],
+        version: u16,
+        flags: u16,
+    }
+
+    unsafe impl Pod for Header {}
+
+    #[repr(C, align(8))]
+    struct Aligned([u8; 16]);
+
+    #[test]
+    fn views_aligned_data() {
+        let data = Aligned(*b"PAHS\x01\x00\x02\x00rest....");
+        let pd = &mut ParseDriver::new();
+        let pos = BytePos::new(&data.0);
+
+        let (new_pos, header) = view::<Header, _>()(pd, pos).unwrap();
+        assert_eq!(new_pos.offset, 8);
+        assert_eq!(&header.magic, b"PAHS");
+        assert_eq!(header.version, u16::from_ne_bytes([1, 0]));
+        assert_eq!(header.flags, u16::from_ne_bytes([2, 0]));
+
+        let (new_pos, err) = view::<u32, _>()(pd, pos.advance_by(1)).unwrap_err();
+        assert_eq!(new_pos.offset, 1);
+        assert_eq!(
+            err,
+            ViewError::Misaligned {
+                offset: 1,
+                alignment: 4
+            }
+        );
+
+        let (_, err) = view::<u64, _>()(pd, pos.advance_by(12)).unwrap_err();
+        assert_eq!(
+            err,
+            ViewError::NotEnoughData {
+                source: NotEnoughDataError {
+                    offset: 12,
+                    needed: 8,
+                    available: 4
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn copies_unaligned_data() {
+        let data = Aligned(*b"_PAHS\x01\x00\x02\x00rest...");
+        let pd = &mut ParseDriver::new();
+        let pos = BytePos::new(&data.0).advance_by(1);
+
+        let (new_pos, header) = view_unaligned::<Header, _>()(pd, pos).unwrap();
+        assert_eq!(new_pos.offset, 9);
+        assert_eq!(&header.magic, b"PAHS");
+        assert_eq!(header.version, u16::from_ne_bytes([1, 0]));
+
+        let (new_pos, err) = view_unaligned::<[u64; 2], _>()(pd, pos).unwrap_err();
+        assert_eq!(new_pos.offset, 1);
+        assert_eq!(
+            err,
+            NotEnoughDataError {
+                offset: 1,
+                needed: 16,
+                available: 15
+            }
+        );
+    }
+
+    #[test]
+    fn views_aligned_slices() {
+        let data = Aligned(*b"\x01\x00\x02\x00\x03\x00\x04\x00rest....");
+        let pd = &mut ParseDriver::new();
+        let pos = BytePos::new(&data.0);
+
+        let (new_pos, vals) = view_slice::<u16, _>(4)(pd, pos).unwrap();
+        assert_eq!(new_pos.offset, 8);
+        assert_eq!(vals.len(), 4);
+        assert_eq!(vals[3], u16::from_ne_bytes([4, 0]));
+
+        let (_, err) = view_slice::<u16, _>(2)(pd, pos.advance_by(1)).unwrap_err();
+        assert_eq!(
+            err,
+            ViewError::Misaligned {
+                offset: 1,
+                alignment: 2
+            }
+        );
+
+        let (_, err) = view_slice::<u16, _>(9)(pd, pos).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "not enough data at offset 0: needed 18, but only 16 left"
+        );
+    }
+
+    #[test]
+    fn views_slices_of_zero_sized_types() {
+        let data = Aligned(*b"any data at all.");
+        let pd = &mut ParseDriver::new();
+        let pos = BytePos::new(&data.0).advance_by(1);
+
+        let (new_pos, vals) = view_slice::<[u32; 0], _>(3)(pd, pos).unwrap();
+        assert_eq!(new_pos.offset, 1);
+        assert_eq!(vals.len(), 3);
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derived_pod_can_be_viewed() {
+        #[derive(Debug, Copy, Clone, crate::slice::Pod)]
+        #[repr(C)]
+        struct Entry {
+            tag: u32,
+            len: u32,
+        }
+
+        let data = Aligned(*b"\x01\x00\x00\x00\x02\x00\x00\x00rest....");
+        let pd = &mut ParseDriver::new();
+        let pos = BytePos::new(&data.0);
+
+        let (_, entry) = view::<Entry, _>()(pd, pos).unwrap();
+        assert_eq!(entry.tag, u32::from_ne_bytes([1, 0, 0, 0]));
+        assert_eq!(entry.len, u32::from_ne_bytes([2, 0, 0, 0]));
+    }
+}
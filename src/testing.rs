@@ -0,0 +1,278 @@
+//! Helpers for unit testing parsers on byte slices.
+//!
+//! Every helper runs the parser with a fresh [`ParseDriver`](ParseDriver) at the start of the
+//! input, and panics with a descriptive message if the expectation isn't met.
+//!
+//! ```
+//! use pahs::slice::num::u16_le;
+//! use pahs::testing::{assert_all_consumed, assert_parses};
+//!
+//! assert_parses(u16_le, &[1, 2, 3], 0x0201, 2);
+//! assert_eq!(assert_all_consumed(u16_le, &[1, 2]), 0x0201);
+//! ```
+//!
+//! With the `proptest` feature enabled, `check_no_panic`, `check_failure_keeps_position` and
+//! `check_round_trip` check properties of a parser for generated inputs.
+
+use std::fmt::Debug;
+
+use crate::slice::BytePos;
+use crate::{ErrorKind, ParseDriver, Progress, Recoverable};
+
+/// Runs `parser` at the start of `input`, with a fresh [`ParseDriver`](ParseDriver).
+#[inline]
+pub fn parse<'a, T, E, F>(parser: F, input: &'a [u8]) -> Progress<BytePos<'a>, T, E>
+where
+    F: FnOnce(&mut ParseDriver, BytePos<'a>) -> Progress<BytePos<'a>, T, E>,
+{
+    parser(&mut ParseDriver::new(), BytePos::new(input))
+}
+
+/// Asserts that `parser` succeeds on `input` with `expected` as value,
+/// consuming exactly `consumed` bytes.
+#[inline]
+#[track_caller]
+pub fn assert_parses<'a, T, E, F>(parser: F, input: &'a [u8], expected: T, consumed: usize)
+where
+    T: PartialEq + Debug,
+    E: Debug,
+    F: FnOnce(&mut ParseDriver, BytePos<'a>) -> Progress<BytePos<'a>, T, E>,
+{
+    let Progress { pos, status } = parse(parser, input);
+    match status {
+        Ok(value) => {
+            assert_eq!(value, expected, "parser returned an unexpected value");
+            assert_eq!(pos.offset, consumed, "parser consumed an unexpected amount");
+        }
+        Err(err) => panic!(
+            "expected the parser to succeed, but it failed at offset {}: {:?}",
+            pos.offset, err
+        ),
+    }
+}
+
+/// Asserts that `parser` fails on `input` at `offset` with an error of `kind`,
+/// returning the error.
+#[inline]
+#[track_caller]
+pub fn assert_fails_at<'a, T, E, F>(parser: F, input: &'a [u8], offset: usize, kind: ErrorKind) -> E
+where
+    T: Debug,
+    E: Recoverable + Debug,
+    F: FnOnce(&mut ParseDriver, BytePos<'a>) -> Progress<BytePos<'a>, T, E>,
+{
+    let Progress { pos, status } = parse(parser, input);
+    match status {
+        Ok(value) => panic!(
+            "expected the parser to fail, but it succeeded at offset {}: {:?}",
+            pos.offset, value
+        ),
+        Err(err) => {
+            assert_eq!(pos.offset, offset, "parser failed at an unexpected offset");
+            assert_eq!(
+                err.kind(),
+                kind,
+                "parser failed with an unexpected error: {:?}",
+                err
+            );
+            err
+        }
+    }
+}
+
+/// Asserts that `parser` succeeds on `input`, consuming all of it, returning the value.
+#[inline]
+#[track_caller]
+pub fn assert_all_consumed<'a, T, E, F>(parser: F, input: &'a [u8]) -> T
+where
+    E: Debug,
+    F: FnOnce(&mut ParseDriver, BytePos<'a>) -> Progress<BytePos<'a>, T, E>,
+{
+    let Progress { pos, status } = parse(parser, input);
+    match status {
+        Ok(value) => {
+            assert!(
+                pos.s.is_empty(),
+                "parser stopped at offset {}, leaving {} bytes unconsumed",
+                pos.offset,
+                pos.s.len()
+            );
+            value
+        }
+        Err(err) => panic!(
+            "expected the parser to succeed, but it failed at offset {}: {:?}",
+            pos.offset, err
+        ),
+    }
+}
+
+#[cfg(feature = "proptest")]
+pub use self::properties::*;
+
+#[cfg(test)]
+pub(crate) mod fixtures;
+
+#[cfg(feature = "proptest")]
+mod properties {
+    use std::fmt::Debug;
+
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::test_runner::{Config, TestRunner};
+
+    use crate::slice::BytePos;
+    use crate::{ParseDriver, Progress};
+
+    /// Generates arbitrary inputs of up to 256 bytes.
+    #[inline]
+    pub fn arbitrary_input() -> impl Strategy<Value = Vec<u8>> {
+        vec(any::<u8>(), 0..=256)
+    }
+
+    fn runner() -> TestRunner {
+        TestRunner::new(Config {
+            failure_persistence: None,
+            ..Config::default()
+        })
+    }
+
+    #[track_caller]
+    fn run<S, F>(strategy: &S, test: F)
+    where
+        S: Strategy,
+        S::Value: Debug,
+        F: Fn(S::Value) -> Result<(), TestCaseError>,
+    {
+        if let Err(err) = runner().run(strategy, test) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Checks that `parser` doesn't panic on arbitrary input, see
+    /// [`arbitrary_input`](arbitrary_input).
+    ///
+    /// Panics with the (shrunk) failing input otherwise.
+    #[inline]
+    #[track_caller]
+    pub fn check_no_panic<T, E, F>(parser: F)
+    where
+        F: for<'a> Fn(&mut ParseDriver, BytePos<'a>) -> Progress<BytePos<'a>, T, E>,
+    {
+        run(&arbitrary_input(), |input| {
+            let _ = parser(&mut ParseDriver::new(), BytePos::new(&input));
+            Ok(())
+        });
+    }
+
+    /// Checks that `parser`, whenever it fails on arbitrary input, reports the failure
+    /// at the position it started at, i.e. that it rewinds on failure.
+    ///
+    /// Panics with the (shrunk) failing input otherwise.
+    #[inline]
+    #[track_caller]
+    pub fn check_failure_keeps_position<T, E, F>(parser: F)
+    where
+        F: for<'a> Fn(&mut ParseDriver, BytePos<'a>) -> Progress<BytePos<'a>, T, E>,
+    {
+        run(&arbitrary_input(), |input| {
+            let Progress { pos, status } = parser(&mut ParseDriver::new(), BytePos::new(&input));
+            if status.is_err() {
+                prop_assert_eq!(pos.offset, 0, "parser failed at a different position");
+            }
+            Ok(())
+        });
+    }
+
+    /// Checks that `parser` parses every value generated by `values` back from its
+    /// serialized form, consuming all of it.
+    ///
+    /// Panics with the (shrunk) failing value otherwise.
+    #[inline]
+    #[track_caller]
+    pub fn check_round_trip<T, E, S, Fs, F>(values: S, serialize: Fs, parser: F)
+    where
+        T: PartialEq + Debug,
+        E: Debug,
+        S: Strategy<Value = T>,
+        Fs: Fn(&T) -> Vec<u8>,
+        F: for<'a> Fn(&mut ParseDriver, BytePos<'a>) -> Progress<BytePos<'a>, T, E>,
+    {
+        run(&values, |value| {
+            let input = serialize(&value);
+            let Progress { pos, status } = parser(&mut ParseDriver::new(), BytePos::new(&input));
+            match status {
+                Ok(parsed) => {
+                    prop_assert_eq!(parsed, value);
+                    prop_assert_eq!(pos.offset, input.len(), "input not fully consumed");
+                }
+                Err(err) => {
+                    return Err(TestCaseError::fail(format!(
+                        "parser failed at offset {}: {:?}",
+                        pos.offset, err
+                    )))
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::slice::num::{u16_le, u8_le};
+    use crate::testing::fixtures::{byte, Error};
+    use crate::ErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn it_asserts_successes() {
+        assert_parses(u16_le, &[1, 2, 3], 0x0201, 2);
+        assert_eq!(assert_all_consumed(u8_le, &[7]), 7);
+    }
+
+    #[test]
+    fn it_asserts_failures() {
+        let err = assert_fails_at(byte(b'a'), b"b", 0, ErrorKind::Backtrack);
+        assert_eq!(err, Error::Expected('a'));
+    }
+
+    #[test]
+    #[should_panic(expected = "parser consumed an unexpected amount")]
+    fn it_checks_the_consumed_bytes() {
+        assert_parses(u8_le, &[1, 2], 1, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "leaving 1 bytes unconsumed")]
+    fn it_checks_that_everything_was_consumed() {
+        assert_all_consumed(u8_le, &[1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected the parser to fail")]
+    fn it_checks_for_failures() {
+        assert_fails_at(byte(b'a'), b"a", 0, ErrorKind::Backtrack);
+    }
+
+    #[cfg(feature = "proptest")]
+    #[test]
+    fn it_checks_properties() {
+        use proptest::prelude::any;
+
+        check_no_panic(u16_le);
+        check_failure_keeps_position(u16_le);
+        check_round_trip(any::<u16>(), |v| v.to_le_bytes().to_vec(), u16_le);
+    }
+
+    #[cfg(feature = "proptest")]
+    #[test]
+    #[should_panic(expected = "parser failed at a different position")]
+    fn it_finds_failures_at_other_positions() {
+        check_failure_keeps_position(|pd, pos| {
+            // fails after the first byte for almost all inputs
+            let (pos, _) = pahs!(u8_le(pd, pos).map_err(|_| Error::Expected('0')));
+            byte(b'b')(pd, pos)
+        });
+    }
+}
@@ -0,0 +1,68 @@
+//! Error types and parsers shared by the unit tests of this crate.
+
+use crate::error_accumulator::{self, Expectation};
+use crate::slice::BytePos;
+use crate::{DriverError, ErrorKind, ParseDriver, Progress, Recoverable};
+
+/// The error of [`byte`](byte).
+#[derive(Debug, PartialEq)]
+pub(crate) enum Error {
+    /// The byte was expected, but not found
+    Expected(char),
+    /// The combinators failed
+    Driver(DriverError),
+}
+
+impl Recoverable for Error {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Expected(..) => ErrorKind::Backtrack,
+            Error::Driver(..) => ErrorKind::Fatal,
+        }
+    }
+
+    fn as_expectation(&self) -> Option<&dyn Expectation> {
+        Some(self)
+    }
+
+    fn from_driver_error(err: DriverError) -> Self {
+        Error::Driver(err)
+    }
+}
+
+impl Expectation for Error {
+    fn expected(&self) -> Vec<error_accumulator::Expected> {
+        match self {
+            Error::Expected(c) => vec![error_accumulator::Expected::Token(c.to_string().into())],
+            Error::Driver(..) => Vec::new(),
+        }
+    }
+}
+
+/// Parses the byte `c`.
+pub(crate) fn byte<'a>(
+    c: u8,
+) -> impl Fn(&mut ParseDriver, BytePos<'a>) -> Progress<BytePos<'a>, u8, Error> {
+    move |_, pos| match pos.s.first() {
+        Some(&b) if b == c => pos.advance_by(1).success(b),
+        _ => pos.failure(Error::Expected(char::from(c))),
+    }
+}
+
+/// An error that is recoverable if it holds `true`, and irrecoverable otherwise.
+#[derive(Debug, PartialEq)]
+pub(crate) struct TestError(pub(crate) bool);
+
+impl Recoverable for TestError {
+    fn kind(&self) -> ErrorKind {
+        if self.0 {
+            ErrorKind::Backtrack
+        } else {
+            ErrorKind::Cut
+        }
+    }
+
+    fn from_driver_error(_err: DriverError) -> Self {
+        TestError(false)
+    }
+}
@@ -0,0 +1,172 @@
+//! Records a tree of parser invocations for debugging grammars.
+//!
+//! Enable tracing via [`ParseDriver::enable_trace`](crate::ParseDriver::enable_trace),
+//! name parsers via [`rule`](crate::combinators::rule), and retrieve the recorded
+//! [`Trace`](Trace) via [`ParseDriver::take_trace`](crate::ParseDriver::take_trace) after
+//! the parse.
+//!
+//! ```text
+//! document [0..7] ok
+//!   value [0..7] ok
+//!     alternate branch 0 [0..0] failed
+//!     alternate branch 1 [0..7] ok
+//!       value [1..1] failed: ExpectedValue
+//! ```
+
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+
+/// A single parser invocation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceNode {
+    /// The name of the parser
+    pub name: Cow<'static, str>,
+    /// The nesting depth, `0` for top-level invocations
+    pub depth: usize,
+    /// The offset at which the parser started
+    pub start: usize,
+    /// The offset at which the parser ended, `None` if it didn't return (yet)
+    pub end: Option<usize>,
+    /// `true` if the parser succeeded
+    pub success: bool,
+    /// A summary of the error if the parser failed, if available
+    pub error: Option<String>,
+}
+
+/// The parser invocations recorded while tracing, in the order they were entered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    nodes: Vec<TraceNode>,
+    open: Vec<usize>,
+}
+
+impl Trace {
+    /// Returns all recorded invocations, in the order they were entered.
+    ///
+    /// Children directly follow their parent, with a larger [`depth`](TraceNode::depth).
+    #[inline]
+    pub fn nodes(&self) -> &[TraceNode] {
+        &self.nodes
+    }
+
+    /// Renders the trace as an indented tree, one invocation per line.
+    #[inline]
+    pub fn to_tree(&self) -> String {
+        let mut out = String::new();
+        // writing to a `String` can't fail
+        self.write_tree(&mut out).unwrap();
+        out
+    }
+
+    /// Renders the trace as JSON lines, one object per invocation.
+    ///
+    /// Every object has the keys `name`, `depth`, `start`, `end`, `success` and `error`.
+    /// `end` and `error` may be `null`.
+    #[inline]
+    pub fn to_json_lines(&self) -> String {
+        let mut out = String::new();
+        // writing to a `String` can't fail
+        self.write_json_lines(&mut out).unwrap();
+        out
+    }
+
+    fn write_tree(&self, out: &mut String) -> fmt::Result {
+        for node in &self.nodes {
+            write!(
+                out,
+                "{:w$}{} [{}..",
+                "",
+                node.name,
+                node.start,
+                w = node.depth * 2
+            )?;
+            match node.end {
+                Some(end) => write!(out, "{}]", end)?,
+                None => out.push_str("?]"),
+            }
+
+            match (node.end, node.success, &node.error) {
+                (None, ..) => out.push_str(" unfinished"),
+                (_, true, _) => out.push_str(" ok"),
+                (_, false, None) => out.push_str(" failed"),
+                (_, false, Some(error)) => write!(out, " failed: {}", error)?,
+            }
+            out.push('\n');
+        }
+        Ok(())
+    }
+
+    fn write_json_lines(&self, out: &mut String) -> fmt::Result {
+        for node in &self.nodes {
+            out.push_str("{\"name\":");
+            write_json_str(out, &node.name)?;
+            write!(out, ",\"depth\":{},\"start\":{}", node.depth, node.start)?;
+            match node.end {
+                Some(end) => write!(out, ",\"end\":{}", end)?,
+                None => out.push_str(",\"end\":null"),
+            }
+            write!(out, ",\"success\":{},\"error\":", node.success)?;
+            match &node.error {
+                Some(error) => write_json_str(out, error)?,
+                None => out.push_str("null"),
+            }
+            out.push_str("}\n");
+        }
+        Ok(())
+    }
+
+    /// Records entering a parser at `start`, returning the id of its node.
+    pub(crate) fn enter(&mut self, name: Cow<'static, str>, start: usize) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(TraceNode {
+            name,
+            depth: self.open.len(),
+            start,
+            end: None,
+            success: false,
+            error: None,
+        });
+        self.open.push(id);
+        id
+    }
+
+    /// Removes all nodes entered after the first `len` ones.
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.nodes.truncate(len);
+        self.open.retain(|&id| id < len);
+    }
+
+    /// Records exiting the parser of node `id` at `end`.
+    ///
+    /// Parsers entered after it that didn't exit yet are left unfinished.
+    pub(crate) fn exit(&mut self, id: usize, end: usize, result: Result<(), Option<String>>) {
+        if let Some(i) = self.open.iter().rposition(|&open| open == id) {
+            self.open.truncate(i);
+        }
+
+        if let Some(node) = self.nodes.get_mut(id) {
+            node.end = Some(end);
+            match result {
+                Ok(()) => node.success = true,
+                Err(error) => node.error = error,
+            }
+        }
+    }
+}
+
+fn write_json_str(out: &mut String, s: &str) -> fmt::Result {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    Ok(())
+}